@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE questions DROP COLUMN IF EXISTS description_html;
+
+ALTER TABLE answers DROP COLUMN IF EXISTS content_html;
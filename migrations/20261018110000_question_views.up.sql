@@ -0,0 +1,3 @@
+-- Add up migration script here
+
+ALTER TABLE questions ADD COLUMN IF NOT EXISTS view_count BIGINT NOT NULL DEFAULT 0;
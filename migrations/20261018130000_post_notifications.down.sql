@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+DROP TRIGGER IF EXISTS answers_notify_delete ON answers;
+DROP TRIGGER IF EXISTS answers_notify_update ON answers;
+DROP TRIGGER IF EXISTS answers_notify_insert ON answers;
+DROP TRIGGER IF EXISTS questions_notify_delete ON questions;
+DROP TRIGGER IF EXISTS questions_notify_update ON questions;
+DROP TRIGGER IF EXISTS questions_notify_insert ON questions;
+DROP FUNCTION IF EXISTS notify_post_change();
@@ -0,0 +1,77 @@
+-- Add up migration script here
+
+-- Tells every instance which posts a statement changed, so each can evict its caches. The origin
+-- is the application_name of the writing connection, which instances set to their own ID.
+--
+-- Statements that change many posts, and bulk writes that set `app.bulk_write`, notify without
+-- the posts, and instances clear their caches instead. The posts are read from the transition
+-- table `changed`, so one statement sends one notification however many rows it changes.
+--
+-- Updates of nothing but view counts are not notified. Views are flushed periodically by every
+-- instance, and the flushing instance evicts its own cache.
+CREATE OR REPLACE FUNCTION notify_post_change() RETURNS trigger AS $$
+DECLARE
+  max_posts CONSTANT INTEGER := 50;
+  changes INTEGER;
+  posts JSON;
+BEGIN
+  -- Only update triggers have the `previous` table
+  IF TG_OP = 'UPDATE' THEN
+    IF NOT EXISTS (
+      SELECT 1 FROM changed JOIN previous ON previous.id = changed.id
+      WHERE to_jsonb(changed) - 'view_count' IS DISTINCT FROM to_jsonb(previous) - 'view_count'
+    ) THEN
+      RETURN NULL;
+    END IF;
+  END IF;
+
+  SELECT count(*) INTO changes FROM (SELECT 1 FROM changed LIMIT max_posts + 1) AS limited;
+
+  IF changes = 0 THEN
+    RETURN NULL;
+  END IF;
+
+  IF changes <= max_posts AND current_setting('app.bulk_write', true) IS DISTINCT FROM 'on' THEN
+    SELECT json_agg(json_build_object(
+      'id', post ->> 'id',
+      'question_id', COALESCE(post ->> 'question_id', post ->> 'id')
+    ))
+    INTO posts
+    FROM (SELECT to_jsonb(changed) AS post FROM changed) AS changed_posts;
+  END IF;
+
+  PERFORM pg_notify('post_changes', json_build_object(
+    'origin', current_setting('application_name'),
+    'table', TG_TABLE_NAME,
+    'operation', lower(TG_OP),
+    'posts', posts
+  )::text);
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Transition tables need a trigger per operation
+CREATE OR REPLACE TRIGGER questions_notify_insert
+  AFTER INSERT ON questions REFERENCING NEW TABLE AS changed
+  FOR EACH STATEMENT EXECUTE FUNCTION notify_post_change();
+
+CREATE OR REPLACE TRIGGER questions_notify_update
+  AFTER UPDATE ON questions REFERENCING OLD TABLE AS previous NEW TABLE AS changed
+  FOR EACH STATEMENT EXECUTE FUNCTION notify_post_change();
+
+CREATE OR REPLACE TRIGGER questions_notify_delete
+  AFTER DELETE ON questions REFERENCING OLD TABLE AS changed
+  FOR EACH STATEMENT EXECUTE FUNCTION notify_post_change();
+
+CREATE OR REPLACE TRIGGER answers_notify_insert
+  AFTER INSERT ON answers REFERENCING NEW TABLE AS changed
+  FOR EACH STATEMENT EXECUTE FUNCTION notify_post_change();
+
+CREATE OR REPLACE TRIGGER answers_notify_update
+  AFTER UPDATE ON answers REFERENCING OLD TABLE AS previous NEW TABLE AS changed
+  FOR EACH STATEMENT EXECUTE FUNCTION notify_post_change();
+
+CREATE OR REPLACE TRIGGER answers_notify_delete
+  AFTER DELETE ON answers REFERENCING OLD TABLE AS changed
+  FOR EACH STATEMENT EXECUTE FUNCTION notify_post_change();
@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+-- One row per `Idempotency-Key` sent with a POST. The key is claimed, and its response stored, in
+-- the transaction of the request, so other requests only ever see completed keys. `token` tells
+-- claims of the same key apart, and expired rows are purged periodically.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+  key TEXT PRIMARY KEY,
+  token UUID NOT NULL DEFAULT GEN_RANDOM_UUID(),
+  fingerprint TEXT NOT NULL,
+  response JSONB,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idempotency_keys_expires_idx
+  ON idempotency_keys (expires_at);
@@ -0,0 +1,220 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use crate::models::{Answer, AnswerId, Question, QuestionId};
+
+/// Number of activities a slow subscriber may fall behind before it starts missing some.
+const CAPACITY: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Activity {
+    QuestionCreated(Question),
+    QuestionDeleted(QuestionId),
+    AnswerCreated(Answer),
+    AnswerDeleted(AnswerId),
+    ViewersChanged {
+        question_id: QuestionId,
+        viewers: usize,
+    },
+}
+
+/// In-process fan-out of everything that happens to questions and answers. Handlers publish
+/// after the change has been stored, so subscribers never see activity that was rolled back.
+#[derive(Clone)]
+pub struct ActivityFeed {
+    sender: broadcast::Sender<Activity>,
+    /// Number of live connections currently following each question.
+    viewers: Arc<Mutex<HashMap<QuestionId, usize>>>,
+}
+
+impl ActivityFeed {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+
+        return Self {
+            sender,
+            viewers: Arc::new(Mutex::new(HashMap::new())),
+        };
+    }
+
+    /// Counts one more viewer of the question and publishes the new count.
+    pub fn join(&self, question_id: QuestionId) {
+        let mut viewers = self.viewers.lock().unwrap();
+        let count = viewers.entry(question_id).or_default();
+
+        *count += 1;
+        self.publish(Activity::ViewersChanged {
+            question_id,
+            viewers: *count,
+        });
+    }
+
+    /// Counts one viewer less of the question and publishes the new count.
+    pub fn leave(&self, question_id: QuestionId) {
+        let mut viewers = self.viewers.lock().unwrap();
+        let Some(count) = viewers.get_mut(&question_id) else {
+            return;
+        };
+
+        *count -= 1;
+        self.publish(Activity::ViewersChanged {
+            question_id,
+            viewers: *count,
+        });
+
+        if *count == 0 {
+            viewers.remove(&question_id);
+        }
+    }
+
+    pub fn viewers(&self, question_id: QuestionId) -> usize {
+        return self
+            .viewers
+            .lock()
+            .unwrap()
+            .get(&question_id)
+            .copied()
+            .unwrap_or_default();
+    }
+
+    pub fn publish(&self, activity: Activity) {
+        // Sending only fails when nobody is subscribed, which is not an error
+        let _ = self.sender.send(activity);
+    }
+
+    /// Whether anyone follows all activity, so relaying is not wasted work.
+    pub fn has_subscribers(&self) -> bool {
+        return self.sender.receiver_count() > 0;
+    }
+
+    /// Every activity published from now on. Activities missed by a lagging subscriber are
+    /// skipped rather than ending the stream.
+    pub fn subscribe(&self) -> impl Stream<Item = Activity> + Send + 'static {
+        return BroadcastStream::new(self.sender.subscribe()).filter_map(Result::ok);
+    }
+
+    pub fn new_questions(&self) -> impl Stream<Item = Question> + Send + 'static {
+        return self.subscribe().filter_map(|activity| match activity {
+            Activity::QuestionCreated(question) => Some(question),
+            _ => None,
+        });
+    }
+
+    pub fn new_answers(
+        &self,
+        question_id: QuestionId,
+    ) -> impl Stream<Item = Answer> + Send + 'static {
+        return self.subscribe().filter_map(move |activity| match activity {
+            Activity::AnswerCreated(answer) if answer.detail.question_uuid == question_id => {
+                Some(answer)
+            }
+            _ => None,
+        });
+    }
+}
+
+impl Default for ActivityFeed {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::AnswerFields;
+
+    fn answer(question_id: QuestionId) -> Answer {
+        return Answer::new(AnswerFields {
+            question_uuid: question_id,
+            content: "test content".to_owned(),
+        });
+    }
+
+    #[tokio::test]
+    async fn new_answers_should_only_include_answers_to_the_question() {
+        let feed = ActivityFeed::new();
+        let question_id = QuestionId(Uuid::new_v4());
+        let expected = answer(question_id);
+        let mut answers = Box::pin(feed.new_answers(question_id));
+
+        feed.publish(Activity::AnswerCreated(answer(QuestionId(Uuid::new_v4()))));
+        feed.publish(Activity::AnswerCreated(expected.clone()));
+
+        assert_eq!(answers.next().await, Some(expected));
+    }
+
+    #[tokio::test]
+    async fn lagging_subscriber_should_skip_missed_activity() {
+        let feed = ActivityFeed::new();
+        let question_id = QuestionId(Uuid::new_v4());
+        let mut activities = Box::pin(feed.subscribe());
+
+        for _ in 0..CAPACITY {
+            feed.publish(Activity::AnswerCreated(answer(question_id)));
+        }
+
+        let latest = answer(question_id);
+
+        feed.publish(Activity::AnswerCreated(latest.clone()));
+
+        let mut received = 0;
+
+        while let Some(activity) = activities.next().await {
+            received += 1;
+
+            if activity == Activity::AnswerCreated(latest.clone()) {
+                break;
+            }
+        }
+
+        assert_eq!(received, CAPACITY);
+    }
+
+    #[tokio::test]
+    async fn joining_and_leaving_should_publish_viewer_counts() {
+        let feed = ActivityFeed::new();
+        let question_id = QuestionId(Uuid::new_v4());
+        let mut activities = Box::pin(feed.subscribe());
+
+        feed.join(question_id);
+        feed.join(question_id);
+        feed.leave(question_id);
+
+        assert_eq!(feed.viewers(question_id), 1);
+
+        for viewers in [1, 2, 1] {
+            assert_eq!(
+                activities.next().await,
+                Some(Activity::ViewersChanged {
+                    question_id,
+                    viewers
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn leaving_unwatched_question_should_be_ignored() {
+        let feed = ActivityFeed::new();
+        let question_id = QuestionId(Uuid::new_v4());
+
+        feed.join(question_id);
+        feed.leave(question_id);
+        feed.leave(question_id);
+
+        assert_eq!(feed.viewers(question_id), 0);
+    }
+
+    #[test]
+    fn publishing_without_subscribers_should_not_panic() {
+        ActivityFeed::new().publish(Activity::AnswerCreated(answer(QuestionId(Uuid::nil()))));
+    }
+}
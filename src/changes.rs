@@ -0,0 +1,447 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::Deserialize;
+use sqlx::{
+    postgres::{PgListener, PgNotification},
+    PgPool,
+};
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    activity::{Activity, ActivityFeed},
+    models::{AnswerId, QuestionId},
+    persistance::{answers_dao::AnswerDAO, cached_dao::DaoCache, questions_dao::QuestionDAO},
+};
+
+/// Channel the `notify_post_change` trigger notifies on.
+pub const CHANNEL: &str = "post_changes";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Table {
+    Questions,
+    Answers,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Operation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A post a statement changed.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+struct Post {
+    id: Uuid,
+    /// The question itself for questions, and the answered question for answers.
+    question_id: Uuid,
+}
+
+/// The changes of one statement, as notified by the `notify_post_change` trigger.
+#[derive(Debug, Deserialize, PartialEq)]
+struct Change {
+    /// `application_name` of the connection that made the change.
+    origin: String,
+    table: Table,
+    operation: Operation,
+    /// The changed posts, or `None` for bulk writes that changed too many to list.
+    posts: Option<Vec<Post>>,
+}
+
+/// Everything a change is applied to.
+pub struct Subscribers {
+    /// `application_name` this instance connects with, to recognize its own changes.
+    pub origin: String,
+    pub cache: Arc<DaoCache>,
+    pub activity: ActivityFeed,
+    pub questions_dao: Arc<dyn QuestionDAO + Send + Sync>,
+    pub answers_dao: Arc<dyn AnswerDAO + Send + Sync>,
+}
+
+impl Subscribers {
+    fn evict(&self, table: Table, operation: Operation, post: Post) {
+        let question_id = QuestionId(post.question_id);
+
+        match (table, operation) {
+            (Table::Questions, Operation::Insert) => self.cache.question_created(),
+            (Table::Questions, Operation::Update) => self.cache.question_changed(question_id),
+            (Table::Questions, Operation::Delete) => self.cache.question_deleted(question_id),
+            (Table::Answers, Operation::Insert | Operation::Update) => {
+                self.cache.answer_created(question_id)
+            }
+            (Table::Answers, Operation::Delete) => self.cache.answer_deleted(AnswerId(post.id)),
+        }
+    }
+
+    /// The activity another instance published for the change, read back from the database
+    /// because notifications are too small to carry posts.
+    async fn activity(&self, table: Table, operation: Operation, post: Post) -> Option<Activity> {
+        let activity = match (table, operation) {
+            (Table::Questions, Operation::Insert) => self
+                .questions_dao
+                .get_question(QuestionId(post.id))
+                .await
+                .map(|detail| Activity::QuestionCreated(detail.question)),
+            (Table::Questions, Operation::Delete) => {
+                Ok(Activity::QuestionDeleted(QuestionId(post.id)))
+            }
+            (Table::Answers, Operation::Insert) => self
+                .answers_dao
+                .get_answer(AnswerId(post.id))
+                .await
+                .map(Activity::AnswerCreated),
+            (Table::Answers, Operation::Delete) => Ok(Activity::AnswerDeleted(AnswerId(post.id))),
+            (_, Operation::Update) => return None,
+        };
+
+        // The post may have been deleted again since
+        return activity
+            .inspect_err(|error| warn!(error = ?error, "Could not read a changed post"))
+            .ok();
+    }
+
+    async fn apply(&self, notification: PgNotification) {
+        let change: Change = match serde_json::from_str(notification.payload()) {
+            Ok(change) => change,
+            Err(error) => {
+                error!(error = %error, payload = notification.payload(), "Malformed change");
+                return;
+            }
+        };
+
+        // Bulk writes, such as imports, are not activity anyone follows, and are not worth
+        // evicting post by post
+        let Some(posts) = change.posts else {
+            self.cache.clear();
+            return;
+        };
+
+        // Writes that bypass the cached DAOs may come from this instance too
+        for post in &posts {
+            self.evict(change.table, change.operation, *post);
+        }
+
+        // Handlers already published what this instance did
+        if change.origin == self.origin || !self.activity.has_subscribers() {
+            return;
+        }
+
+        for post in posts {
+            if let Some(activity) = self.activity(change.table, change.operation, post).await {
+                self.activity.publish(activity);
+            }
+        }
+    }
+}
+
+#[instrument(skip_all)]
+async fn listen(pool: PgPool, subscribers: Subscribers) {
+    while !pool.is_closed() {
+        let mut listener = match PgListener::connect_with(&pool).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                error!(error = ?error, "Could not connect the change listener");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+
+        if let Err(error) = listener.listen(CHANNEL).await {
+            error!(error = ?error, "Could not listen for changes");
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            continue;
+        }
+
+        // Changes made while not listening are lost, so anything cached until now may be stale
+        subscribers.cache.clear();
+        info!("Listening for changes");
+
+        loop {
+            match listener.try_recv().await {
+                Ok(Some(notification)) => subscribers.apply(notification).await,
+                // Reconnect here rather than on the next receive, so the cache is cleared once
+                // listening again
+                Ok(None) => {
+                    warn!("Change listener lost its connection");
+                    break;
+                }
+                Err(error) => {
+                    error!(error = ?error, "Change listener failed");
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Applies changes notified by every instance, including this one, until the pool is closed.
+pub fn spawn_listener(pool: PgPool, subscribers: Subscribers) {
+    tokio::spawn(listen(pool, subscribers));
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{postgres::PgPoolOptions, PgPool};
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::{
+        models::{ExportRecord, Question, QuestionFields},
+        persistance::{
+            answers_dao,
+            backup_dao::{self, BackupDAO},
+            cached_dao::CachedDAO,
+            questions_dao,
+        },
+    };
+
+    async fn other_instance(pool: &PgPool) -> Result<PgPool, String> {
+        return PgPoolOptions::new()
+            .connect_with(
+                pool.connect_options()
+                    .as_ref()
+                    .clone()
+                    .application_name("other instance"),
+            )
+            .await
+            .map_err(|e| format!("{:?}", e));
+    }
+
+    /// Waits until some connection to the test database listens for changes.
+    async fn listening(pool: &PgPool) -> Result<(), String> {
+        let poll = async {
+            loop {
+                let listening = sqlx::query_scalar!(
+                    r#"
+                        SELECT EXISTS (
+                            SELECT 1 FROM pg_stat_activity
+                            WHERE datname = current_database() AND query = $1
+                        ) AS "listening!"
+                    "#,
+                    format!(r#"LISTEN "{}""#, CHANNEL)
+                )
+                .fetch_one(pool)
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+
+                if listening {
+                    return Ok(());
+                }
+
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+
+        return tokio::time::timeout(Duration::from_secs(5), poll)
+            .await
+            .map_err(|_| "The listener did not subscribe.".to_owned())?;
+    }
+
+    #[test]
+    fn change_should_parse_trigger_payload() {
+        let id = Uuid::new_v4();
+        let question_id = Uuid::new_v4();
+        let payload = format!(
+            r#"{{"origin": "a", "table": "answers", "operation": "delete", "posts": [{{"id": "{}", "question_id": "{}"}}]}}"#,
+            id, question_id
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Change>(&payload).unwrap(),
+            Change {
+                origin: "a".to_owned(),
+                table: Table::Answers,
+                operation: Operation::Delete,
+                posts: Some(vec![Post { id, question_id }]),
+            }
+        );
+    }
+
+    #[test]
+    fn bulk_change_should_parse_without_posts() {
+        let payload =
+            r#"{"origin": "a", "table": "questions", "operation": "insert", "posts": null}"#;
+
+        assert_eq!(serde_json::from_str::<Change>(payload).unwrap().posts, None);
+    }
+
+    #[sqlx::test]
+    async fn changes_from_other_instances_should_evict_and_relay(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let cache = Arc::new(DaoCache::new(
+            std::num::NonZeroUsize::new(10).unwrap(),
+            Duration::from_secs(60),
+        ));
+        let questions_dao = Arc::new(CachedDAO::new(
+            questions_dao::DAO::new(pool.clone()),
+            cache.clone(),
+        ));
+        let activity = ActivityFeed::new();
+        let mut new_questions = Box::pin(activity.new_questions());
+
+        spawn_listener(
+            pool.clone(),
+            Subscribers {
+                origin: "this instance".to_owned(),
+                cache,
+                activity: activity.clone(),
+                questions_dao: questions_dao.clone(),
+                answers_dao: Arc::new(answers_dao::DAO::new(pool.clone())),
+            },
+        );
+
+        // Nothing may change before the listener subscribes
+        listening(&pool).await?;
+
+        let cached = questions_dao
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if !cached.is_empty() {
+            return Err("Expected no questions.".to_owned());
+        }
+
+        // Another instance writes through its own connection, bypassing this cache
+        let question = questions_dao::DAO::new(other_instance(&pool).await?)
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let relayed = tokio::time::timeout(Duration::from_secs(5), new_questions.next())
+            .await
+            .map_err(|_| "The question was not relayed.".to_owned())?;
+
+        if relayed != Some(question.clone()) {
+            return Err(format!("Incorrect question relayed: {:?}", relayed));
+        }
+
+        let questions = questions_dao
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if questions != vec![question] {
+            return Err("The cached questions were not evicted.".to_owned());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn bulk_writes_should_clear_cache_without_relaying(pool: PgPool) -> Result<(), String> {
+        let cache = Arc::new(DaoCache::new(
+            std::num::NonZeroUsize::new(10).unwrap(),
+            Duration::from_secs(60),
+        ));
+        let questions_dao = Arc::new(CachedDAO::new(
+            questions_dao::DAO::new(pool.clone()),
+            cache.clone(),
+        ));
+        let activity = ActivityFeed::new();
+        let mut new_questions = Box::pin(activity.new_questions());
+
+        spawn_listener(
+            pool.clone(),
+            Subscribers {
+                origin: "this instance".to_owned(),
+                cache,
+                activity: activity.clone(),
+                questions_dao: questions_dao.clone(),
+                answers_dao: Arc::new(answers_dao::DAO::new(pool.clone())),
+            },
+        );
+
+        // Nothing may change before the listener subscribes
+        listening(&pool).await?;
+
+        questions_dao
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        backup_dao::DAO::new(other_instance(&pool).await?)
+            .merge(vec![ExportRecord::Question(Question {
+                question_uuid: QuestionId(Uuid::new_v4()),
+                detail: QuestionFields {
+                    title: "test title".to_owned(),
+                    description: "test description".to_owned(),
+                },
+                description_html: String::new(),
+                created_at: chrono::offset::Utc::now(),
+            })])
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let relayed = tokio::time::timeout(Duration::from_secs(1), new_questions.next()).await;
+
+        if let Ok(relayed) = relayed {
+            return Err(format!("A bulk write was relayed: {:?}", relayed));
+        }
+
+        let questions = questions_dao
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if questions.len() != 1 {
+            return Err("The cached questions were not cleared.".to_owned());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn view_count_updates_should_not_be_notified(pool: PgPool) -> Result<(), String> {
+        let dao = questions_dao::DAO::new(pool.clone());
+        let question = dao
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let mut listener = PgListener::connect_with(&pool)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        listener
+            .listen(CHANNEL)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        dao.add_views(std::collections::HashMap::from([(
+            question.question_uuid,
+            1,
+        )]))
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+        dao.delete_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        // Notifications arrive in commit order, so the deletion comes first unless views were
+        // notified too
+        let notification = listener.recv().await.map_err(|e| format!("{:?}", e))?;
+        let change: Change =
+            serde_json::from_str(notification.payload()).map_err(|e| format!("{:?}", e))?;
+
+        if change.operation != Operation::Delete {
+            return Err(format!("Views were notified: {:?}", change));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,378 @@
+use chrono::NaiveDateTime;
+use quick_xml::{
+    escape::{unescape, EscapeError},
+    events::{attributes::AttrError, BytesStart, Event},
+    Reader,
+};
+use tokio::io::AsyncBufRead;
+use tracing::{info, warn};
+use uuid::{uuid, Uuid};
+use validator::Validate;
+
+use crate::{models::*, persistance::backup_dao::BackupDAO};
+
+/// Posts stored per transaction.
+pub const BATCH_SIZE: usize = 1000;
+
+/// Namespace of the per-site namespaces of IDs derived from Stack Exchange post IDs. Never
+/// change it, or a second import of the same dump would duplicate every post.
+const POST_NAMESPACE: Uuid = uuid!("6f1d4f64-3a48-4b8e-9d55-0c6e2b4f5a1e");
+
+const QUESTION_TYPE: &str = "1";
+const ANSWER_TYPE: &str = "2";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DumpError {
+    #[error("Malformed XML: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("Malformed attribute: {0}")]
+    Attribute(#[from] AttrError),
+    #[error("Attribute is not properly escaped: {0}")]
+    Escape(#[from] EscapeError),
+    #[error("Post {id} is missing the {attribute} attribute")]
+    MissingAttribute { id: String, attribute: &'static str },
+    #[error("Post {id} has an invalid {attribute}: {value}")]
+    InvalidAttribute {
+        id: String,
+        attribute: &'static str,
+        value: String,
+    },
+    #[error(transparent)]
+    Database(#[from] DBError),
+}
+
+/// Running totals of an import, logged after every batch.
+#[derive(Debug, Default, PartialEq)]
+pub struct Progress {
+    /// `<row>` elements read so far.
+    pub rows: u64,
+    pub questions: u64,
+    pub answers: u64,
+    /// Rows that are neither questions nor answers, fail validation, were stored before, or
+    /// answer a question that is not in the dump.
+    pub skipped: u64,
+}
+
+/// Namespace of the post IDs of a Stack Exchange site, such as `stackoverflow.com`. Post IDs are
+/// only unique per site, so dumps of different sites must not share a namespace.
+pub fn site_namespace(site: &str) -> Uuid {
+    return Uuid::new_v5(&POST_NAMESPACE, site.as_bytes());
+}
+
+/// Stable ID of a Stack Exchange post, so importing a dump twice stores each post once.
+pub fn post_uuid(namespace: &Uuid, post_id: &str) -> Uuid {
+    return Uuid::new_v5(namespace, post_id.as_bytes());
+}
+
+/// Raw attributes of a `<row>` in `Posts.xml`.
+#[derive(Debug, Default)]
+struct Row {
+    id: Option<String>,
+    post_type_id: Option<String>,
+    parent_id: Option<String>,
+    creation_date: Option<String>,
+    title: Option<String>,
+    body: Option<String>,
+}
+
+impl Row {
+    fn parse(element: &BytesStart) -> Result<Self, DumpError> {
+        let mut row = Self::default();
+
+        for attribute in element.attributes() {
+            let attribute = attribute?;
+            let field = match attribute.key.as_ref() {
+                "Id" => &mut row.id,
+                "PostTypeId" => &mut row.post_type_id,
+                "ParentId" => &mut row.parent_id,
+                "CreationDate" => &mut row.creation_date,
+                "Title" => &mut row.title,
+                "Body" => &mut row.body,
+                _ => continue,
+            };
+
+            // Unescaped as is, because bodies are HTML whose line breaks must survive
+            *field = Some(unescape(&attribute.value)?.into_owned());
+        }
+
+        return Ok(row);
+    }
+
+    /// Maps questions and answers onto our models. Every other post type yields `None`.
+    fn into_record(self, namespace: &Uuid) -> Result<Option<ExportRecord>, DumpError> {
+        let Self {
+            id,
+            post_type_id,
+            parent_id,
+            creation_date,
+            title,
+            body,
+        } = self;
+        let id = id.ok_or_else(|| missing(String::new(), "Id"))?;
+        let post_type_id = post_type_id.ok_or_else(|| missing(id.clone(), "PostTypeId"))?;
+
+        if post_type_id != QUESTION_TYPE && post_type_id != ANSWER_TYPE {
+            return Ok(None);
+        }
+
+        let creation_date = creation_date.ok_or_else(|| missing(id.clone(), "CreationDate"))?;
+        // Dump dates are UTC without an offset
+        let created_at = NaiveDateTime::parse_from_str(&creation_date, "%Y-%m-%dT%H:%M:%S%.f")
+            .map_err(|_| DumpError::InvalidAttribute {
+                id: id.clone(),
+                attribute: "CreationDate",
+                value: creation_date,
+            })?
+            .and_utc();
+        // Bodies are HTML, which markdown passes through to the sanitizer
+        let body = body.ok_or_else(|| missing(id.clone(), "Body"))?;
+
+        if post_type_id == QUESTION_TYPE {
+            let title = title.ok_or_else(|| missing(id.clone(), "Title"))?;
+
+            return Ok(Some(ExportRecord::Question(Question {
+                question_uuid: QuestionId(post_uuid(namespace, &id)),
+                detail: QuestionFields {
+                    title,
+                    description: body,
+                },
+                // Rendered when stored
+                description_html: String::new(),
+                created_at,
+            })));
+        }
+
+        let parent_id = parent_id.ok_or_else(|| missing(id.clone(), "ParentId"))?;
+
+        return Ok(Some(ExportRecord::Answer(Answer {
+            answer_uuid: AnswerId(post_uuid(namespace, &id)),
+            detail: AnswerFields {
+                question_uuid: QuestionId(post_uuid(namespace, &parent_id)),
+                content: body,
+            },
+            content_html: String::new(),
+            created_at,
+        })));
+    }
+}
+
+fn missing(id: String, attribute: &'static str) -> DumpError {
+    return DumpError::MissingAttribute { id, attribute };
+}
+
+async fn store(
+    batch: &mut Vec<ExportRecord>,
+    progress: &mut Progress,
+    dao: &(dyn BackupDAO + Send + Sync),
+) -> Result<(), DumpError> {
+    let records = batch.len() as u64;
+    let summary = dao.merge(std::mem::take(batch)).await?;
+    let stored = (summary.questions + summary.answers) as u64;
+
+    progress.questions += summary.questions as u64;
+    progress.answers += summary.answers as u64;
+    progress.skipped += records - stored;
+
+    info!(
+        rows = progress.rows,
+        questions = progress.questions,
+        answers = progress.answers,
+        skipped = progress.skipped,
+        "Imported a batch of posts"
+    );
+
+    return Ok(());
+}
+
+/// Streams the `Posts.xml` of a Stack Exchange site into the questions and answers tables, one
+/// batch per transaction. Questions precede their answers in the dump, so batches can be stored
+/// in order. Posts that fail validation are logged and skipped.
+pub async fn import_posts(
+    source: impl AsyncBufRead + Unpin,
+    site: &str,
+    dao: &(dyn BackupDAO + Send + Sync),
+) -> Result<Progress, DumpError> {
+    let namespace = site_namespace(site);
+    let mut reader = Reader::from_reader(source);
+    let mut buffer = Vec::new();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut progress = Progress::default();
+
+    loop {
+        match reader.read_event_into_async(&mut buffer).await? {
+            Event::Empty(element) | Event::Start(element) if element.name().as_ref() == "row" => {
+                progress.rows += 1;
+
+                let row = Row::parse(&element)?;
+                let id = row.id.clone().unwrap_or_default();
+
+                match row.into_record(&namespace)? {
+                    Some(record) => match record.validate() {
+                        Ok(()) => batch.push(record),
+                        Err(errors) => {
+                            warn!(
+                                post = id,
+                                errors = %errors,
+                                "Skipped a post that failed validation"
+                            );
+                            progress.skipped += 1;
+                        }
+                    },
+                    None => progress.skipped += 1,
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buffer.clear();
+
+        if batch.len() >= BATCH_SIZE {
+            store(&mut batch, &mut progress, dao).await?;
+        }
+    }
+
+    if !batch.is_empty() {
+        store(&mut batch, &mut progress, dao).await?;
+    }
+
+    return Ok(progress);
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::persistance::{
+        backup_dao::DAO as BackupDaoImpl,
+        questions_dao::{QuestionDAO, DAO as QuestionsDaoImpl},
+    };
+
+    const POSTS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<posts>
+  <row Id="1" PostTypeId="1" CreationDate="2008-07-31T21:42:52.667" Score="1" Body="&lt;p&gt;How do I &amp;amp; why?&lt;/p&gt;&#xA;&#xA;&lt;pre&gt;&lt;code&gt;a&#xA;b&lt;/code&gt;&lt;/pre&gt;&#xA;" Title="Convert a decimal to a double" Tags="&lt;c#&gt;" />
+  <row Id="2" PostTypeId="2" ParentId="1" CreationDate="2008-07-31T22:17:57.883" Body="&lt;p&gt;Like this, with a cast in between.&lt;/p&gt;&#xA;" />
+  <row Id="3" PostTypeId="4" CreationDate="2008-07-31T23:00:00.000" Body="Tag wiki excerpt" />
+  <row Id="4" PostTypeId="2" ParentId="99" CreationDate="2008-08-01T00:00:00.000" Body="&lt;p&gt;An answer to a question that is gone.&lt;/p&gt;" />
+  <row Id="5" PostTypeId="1" CreationDate="2008-08-01T00:00:00.000" Body="&lt;p&gt;A question with a title that is far too short.&lt;/p&gt;" Title="Help" />
+</posts>
+"#;
+
+    fn namespace() -> Uuid {
+        return site_namespace("stackoverflow.com");
+    }
+
+    fn row(xml: &str) -> Result<Option<ExportRecord>, DumpError> {
+        let mut reader = Reader::from_str(xml);
+
+        return match reader.read_event().unwrap() {
+            Event::Empty(element) => Row::parse(&element)?.into_record(&namespace()),
+            event => panic!("Expected a row but got {:?}", event),
+        };
+    }
+
+    #[test]
+    fn question_row_should_keep_body_line_breaks() {
+        let record = row(POSTS.lines().nth(2).unwrap().trim()).unwrap();
+
+        match record {
+            Some(ExportRecord::Question(question)) => {
+                assert_eq!(
+                    question.question_uuid,
+                    QuestionId(post_uuid(&namespace(), "1"))
+                );
+                assert_eq!(question.detail.title, "Convert a decimal to a double");
+                assert_eq!(
+                    question.detail.description,
+                    "<p>How do I &amp; why?</p>\n\n<pre><code>a\nb</code></pre>\n"
+                );
+                assert_eq!(
+                    question.created_at.to_rfc3339(),
+                    "2008-07-31T21:42:52.667+00:00"
+                );
+            }
+            record => panic!("Expected a question but got {:?}", record),
+        }
+    }
+
+    #[test]
+    fn answer_row_should_reference_its_question() {
+        match row(POSTS.lines().nth(3).unwrap().trim()).unwrap() {
+            Some(ExportRecord::Answer(answer)) => {
+                assert_eq!(answer.answer_uuid, AnswerId(post_uuid(&namespace(), "2")));
+                assert_eq!(
+                    answer.detail.question_uuid,
+                    QuestionId(post_uuid(&namespace(), "1"))
+                );
+            }
+            record => panic!("Expected an answer but got {:?}", record),
+        }
+    }
+
+    #[test]
+    fn posts_of_different_sites_should_not_share_ids() {
+        assert_ne!(
+            post_uuid(&site_namespace("stackoverflow.com"), "1"),
+            post_uuid(&site_namespace("superuser.com"), "1")
+        );
+    }
+
+    #[test]
+    fn other_post_types_should_be_skipped() {
+        assert!(row(POSTS.lines().nth(4).unwrap().trim()).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_rows_should_be_rejected() {
+        assert!(matches!(
+            row(r#"<row Id="5" PostTypeId="1" CreationDate="2008-07-31" Body="" Title="" />"#),
+            Err(DumpError::InvalidAttribute {
+                attribute: "CreationDate",
+                ..
+            })
+        ));
+        assert!(matches!(
+            row(r#"<row Id="6" PostTypeId="2" CreationDate="2008-07-31T21:42:52.667" Body="" />"#),
+            Err(DumpError::MissingAttribute {
+                attribute: "ParentId",
+                ..
+            })
+        ));
+    }
+
+    #[sqlx::test]
+    async fn import_posts_should_be_repeatable(pool: PgPool) -> Result<(), String> {
+        let dao = BackupDaoImpl::new(pool.clone());
+
+        let progress = import_posts(POSTS.as_bytes(), "stackoverflow.com", &dao)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        assert_eq!(
+            progress,
+            Progress {
+                rows: 5,
+                questions: 1,
+                answers: 1,
+                skipped: 3,
+            }
+        );
+
+        let progress = import_posts(POSTS.as_bytes(), "stackoverflow.com", &dao)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        assert_eq!(progress.skipped, 5);
+
+        let question = QuestionsDaoImpl::new(pool)
+            .get_question(QuestionId(post_uuid(&namespace(), "1")))
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        assert_eq!(question.answer_count, 1);
+        assert!(question.question.description_html.contains("<pre>"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Number of entries in every feed, newest first.
+pub const FEED_SIZE: usize = 20;
+
+pub struct Feed {
+    pub title: String,
+    /// Absolute URL of the feed itself.
+    pub url: String,
+    /// Absolute URL of the resource the feed describes.
+    pub link: String,
+    pub updated: DateTime<Utc>,
+    pub entries: Vec<Entry>,
+}
+
+pub struct Entry {
+    pub id: Uuid,
+    pub title: String,
+    pub link: String,
+    pub published: DateTime<Utc>,
+    /// Already sanitized HTML.
+    pub content_html: String,
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    return escaped;
+}
+
+impl Feed {
+    pub fn to_atom(&self) -> String {
+        let mut xml = String::new();
+
+        // Writing to a String cannot fail
+        write!(
+            xml,
+            concat!(
+                r#"<?xml version="1.0" encoding="utf-8"?>"#,
+                r#"<feed xmlns="http://www.w3.org/2005/Atom">"#,
+                "<id>{url}</id><title>{title}</title><updated>{updated}</updated>",
+                r#"<link rel="self" href="{url}"/><link rel="alternate" href="{link}"/>"#,
+            ),
+            url = escape(&self.url),
+            title = escape(&self.title),
+            updated = self.updated.to_rfc3339(),
+            link = escape(&self.link),
+        )
+        .unwrap();
+
+        for entry in &self.entries {
+            write!(
+                xml,
+                concat!(
+                    "<entry><id>urn:uuid:{id}</id><title>{title}</title>",
+                    "<published>{published}</published><updated>{published}</updated>",
+                    r#"<link rel="alternate" href="{link}"/>"#,
+                    r#"<content type="html">{content}</content></entry>"#,
+                ),
+                id = entry.id,
+                title = escape(&entry.title),
+                published = entry.published.to_rfc3339(),
+                link = escape(&entry.link),
+                content = escape(&entry.content_html),
+            )
+            .unwrap();
+        }
+
+        xml.push_str("</feed>");
+
+        return xml;
+    }
+
+    pub fn to_rss(&self) -> String {
+        let mut xml = String::new();
+
+        write!(
+            xml,
+            concat!(
+                r#"<?xml version="1.0" encoding="utf-8"?>"#,
+                r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"><channel>"#,
+                "<title>{title}</title><link>{link}</link><description>{title}</description>",
+                r#"<atom:link rel="self" href="{url}" type="application/rss+xml"/>"#,
+                "<lastBuildDate>{updated}</lastBuildDate>",
+            ),
+            title = escape(&self.title),
+            link = escape(&self.link),
+            url = escape(&self.url),
+            updated = self.updated.to_rfc2822(),
+        )
+        .unwrap();
+
+        for entry in &self.entries {
+            write!(
+                xml,
+                concat!(
+                    "<item><title>{title}</title><link>{link}</link>",
+                    r#"<guid isPermaLink="false">urn:uuid:{id}</guid>"#,
+                    "<pubDate>{published}</pubDate>",
+                    "<description>{content}</description></item>",
+                ),
+                title = escape(&entry.title),
+                link = escape(&entry.link),
+                id = entry.id,
+                published = entry.published.to_rfc2822(),
+                content = escape(&entry.content_html),
+            )
+            .unwrap();
+        }
+
+        xml.push_str("</channel></rss>");
+
+        return xml;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed() -> Feed {
+        let published = DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        return Feed {
+            title: "Newest questions".to_owned(),
+            url: "http://localhost/questions/feed.atom".to_owned(),
+            link: "http://localhost/questions".to_owned(),
+            updated: published,
+            entries: vec![Entry {
+                id: Uuid::nil(),
+                title: "Why is <T> & 'a needed?".to_owned(),
+                link: "http://localhost/question/1".to_owned(),
+                published,
+                content_html: "<p>Body</p>".to_owned(),
+            }],
+        };
+    }
+
+    #[test]
+    fn atom_should_escape_titles_and_content() {
+        let atom = feed().to_atom();
+
+        assert!(atom.contains("<title>Why is &lt;T&gt; &amp; &apos;a needed?</title>"));
+        assert!(atom.contains(r#"<content type="html">&lt;p&gt;Body&lt;/p&gt;</content>"#));
+        assert!(atom.contains("<updated>2026-10-18T12:00:00+00:00</updated>"));
+        assert!(atom.contains("<id>urn:uuid:00000000-0000-0000-0000-000000000000</id>"));
+    }
+
+    #[test]
+    fn rss_should_use_rfc_2822_dates() {
+        let rss = feed().to_rss();
+
+        assert!(rss.contains("<pubDate>Sun, 18 Oct 2026 12:00:00 +0000</pubDate>"));
+        assert!(rss.contains("<description>&lt;p&gt;Body&lt;/p&gt;</description>"));
+        assert!(rss.ends_with("</item></channel></rss>"));
+    }
+}
@@ -0,0 +1,176 @@
+use axum::{
+    body::Body,
+    extract::{rejection::QueryRejection, Query, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use tokio_stream::StreamExt;
+use tracing::instrument;
+
+use crate::{models::*, AppState};
+
+use super::{
+    conditional::to_json,
+    extractors::Admin,
+    inner::{self, HandlerError},
+};
+
+/// Imports are far larger than any other request body, so they bypass the default limit.
+const MAX_IMPORT_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct ImportOptions {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/export",
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses(
+        (
+            status = 200,
+            description = "Every question followed by every answer, one JSON record per line",
+            body = ExportRecord,
+            content_type = "application/x-ndjson"
+        ),
+        (
+            status = 401,
+            description = "No admin token was given",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "The admin token is not valid or admin endpoints are disabled",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn export_data(
+    _: Admin,
+    State(AppState { backup_dao, .. }): State<AppState>,
+) -> impl IntoResponse {
+    // The status is sent before the first record, so a failure part way through can only
+    // abort the response
+    let lines = backup_dao.export().map(|record| {
+        return record.map(|record| {
+            let mut line = to_json(&record).into_bytes();
+            line.push(b'\n');
+            return line;
+        });
+    });
+
+    return (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"export.jsonl\"",
+            ),
+        ],
+        Body::from_stream(lines),
+    );
+}
+
+async fn read_body(body: Body) -> Result<String, HandlerError> {
+    let mut chunks = body.into_data_stream();
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|error| {
+            return HandlerError::BadRequest(format!("Failed to read the request body: {}", error));
+        })?;
+
+        if bytes.len() + chunk.len() > MAX_IMPORT_SIZE {
+            return Err(HandlerError::PayloadTooLarge(format!(
+                "Imports are limited to {} bytes.",
+                MAX_IMPORT_SIZE
+            )));
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    return String::from_utf8(bytes)
+        .map_err(|_| HandlerError::BadRequest(String::from("The import is not valid UTF-8.")));
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/import",
+    tag = "admin",
+    security(("admin_token" = [])),
+    params(("dry_run" = Option<bool>, Query, description = "Validate the import without keeping it")),
+    request_body(
+        content = ExportRecord,
+        description = "Records in the export format, one per line",
+        content_type = "application/x-ndjson"
+    ),
+    responses(
+        (status = 200, description = "Every record was imported", body = ImportSummary),
+        (
+            status = 400,
+            description = "The import is not valid UTF-8",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 401,
+            description = "No admin token was given",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "The admin token is not valid or admin endpoints are disabled",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 409,
+            description = "A record with the same ID is already stored; nothing was imported",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 413,
+            description = "The import is too large",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 422,
+            description = "A line is not a valid record, a record failed validation, or an answer \
+                has no question; nothing was imported",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn import_data(
+    _: Admin,
+    State(AppState { backup_dao, .. }): State<AppState>,
+    options: Result<Query<ImportOptions>, QueryRejection>,
+    body: Body,
+) -> Result<Json<ImportSummary>, HandlerError> {
+    let Query(options) = options?;
+    let lines = read_body(body).await?;
+
+    let summary = inner::import_records(&lines, options.dry_run, backup_dao.as_ref()).await?;
+
+    return Ok(Json(summary));
+}
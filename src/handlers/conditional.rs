@@ -0,0 +1,230 @@
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::inner::HandlerError;
+
+pub fn http_date(date: DateTime<Utc>) -> String {
+    return date.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+}
+
+/// Whether the client's copy, per `If-Modified-Since`, is at least as new as `updated`. HTTP
+/// dates have whole second precision.
+pub fn is_fresh(headers: &HeaderMap, updated: DateTime<Utc>) -> bool {
+    return headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| updated.timestamp() <= since.timestamp());
+}
+
+/// Strong validator of a representation: the quoted hex SHA-256 of its bytes.
+pub fn etag(body: &[u8]) -> String {
+    return format!("\"{}\"", hex::encode(Sha256::digest(body)));
+}
+
+/// Serializes a model or message that is sent or stored as JSON.
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    // Panic if a model cannot be serialized, which can only be a bug
+    return serde_json::to_string(value).expect("Models should serialize to JSON");
+}
+
+/// Whether a list of entity tags such as `If-None-Match` names `etag`. The weak comparison
+/// ignores `W/` prefixes, while the strong one never matches weak tags.
+fn list_matches(list: &str, etag: &str, weak: bool) -> bool {
+    return list.split(',').map(str::trim).any(|candidate| {
+        if candidate == "*" {
+            return true;
+        }
+
+        return match candidate.strip_prefix("W/") {
+            Some(tag) => weak && tag == etag,
+            None => candidate == etag,
+        };
+    });
+}
+
+/// Whether the client's copy is current. `If-None-Match` takes precedence over
+/// `If-Modified-Since`, because dates cannot tell apart changes within a second.
+pub fn is_not_modified(
+    headers: &HeaderMap,
+    etag: &str,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    if let Some(list) = headers.get(header::IF_NONE_MATCH) {
+        return list
+            .to_str()
+            .is_ok_and(|list| list_matches(list, etag, true));
+    }
+
+    return last_modified.is_some_and(|last_modified| is_fresh(headers, last_modified));
+}
+
+/// Responds with `value` as JSON along with its validators, or with 304 when the client's copy
+/// is current.
+pub fn conditional_json<T: Serialize>(
+    value: &T,
+    last_modified: Option<DateTime<Utc>>,
+    headers: &HeaderMap,
+) -> Response {
+    return conditional_json_of(value, value, last_modified, headers);
+}
+
+/// Like `conditional_json`, but with an ETag over `validated` only, the part of `value` that
+/// conditional requests should tell changes of.
+pub fn conditional_json_of<T: Serialize, V: Serialize>(
+    value: &T,
+    validated: &V,
+    last_modified: Option<DateTime<Utc>>,
+    headers: &HeaderMap,
+) -> Response {
+    let body = to_json(value).into_bytes();
+    let etag = etag(to_json(validated).as_bytes());
+    let mut validators = HeaderMap::new();
+
+    // Hex digits and quotes are always valid header characters
+    validators.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+
+    if let Some(last_modified) = last_modified {
+        validators.insert(
+            header::LAST_MODIFIED,
+            HeaderValue::from_str(&http_date(last_modified)).unwrap(),
+        );
+    }
+
+    if is_not_modified(headers, &etag, last_modified) {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+
+    return (
+        validators,
+        [(header::CONTENT_TYPE, "application/json")],
+        body,
+    )
+        .into_response();
+}
+
+/// The `If-Match` condition of a request, if it has one.
+pub fn if_match(headers: &HeaderMap) -> Option<&str> {
+    return headers
+        .get(header::IF_MATCH)
+        .map(|value| value.to_str().unwrap_or_default());
+}
+
+/// Fails unless the `If-Match` condition names the current representation, so clients only
+/// change what they last read.
+pub fn check_if_match<T: Serialize>(condition: &str, current: &T) -> Result<(), HandlerError> {
+    if list_matches(condition, &etag(to_json(current).as_bytes()), false) {
+        return Ok(());
+    }
+
+    return Err(HandlerError::PreconditionFailed(String::from(
+        "The resource has changed since it was read.",
+    )));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+
+        return headers;
+    }
+
+    fn updated() -> DateTime<Utc> {
+        return DateTime::parse_from_rfc3339("2026-10-18T12:00:00.250Z")
+            .unwrap()
+            .to_utc();
+    }
+
+    #[test]
+    fn http_date_should_use_gmt() {
+        assert_eq!(http_date(updated()), "Sun, 18 Oct 2026 12:00:00 GMT");
+    }
+
+    #[test]
+    fn resource_should_be_fresh_when_not_modified_since() {
+        let since = |value| headers(header::IF_MODIFIED_SINCE, value);
+
+        assert!(is_fresh(&since("Sun, 18 Oct 2026 12:00:00 GMT"), updated()));
+        assert!(is_fresh(&since("Sun, 18 Oct 2026 13:00:00 GMT"), updated()));
+        assert!(!is_fresh(
+            &since("Sun, 18 Oct 2026 11:59:59 GMT"),
+            updated()
+        ));
+        assert!(!is_fresh(&since("not a date"), updated()));
+        assert!(!is_fresh(&HeaderMap::new(), updated()));
+    }
+
+    #[test]
+    fn etag_should_be_quoted_and_stable() {
+        let tag = etag(b"[]");
+
+        assert!(tag.starts_with('"') && tag.ends_with('"'));
+        assert_eq!(tag, etag(b"[]"));
+        assert_ne!(tag, etag(b"{}"));
+    }
+
+    #[test]
+    fn matching_etag_should_not_be_sent_again() {
+        let tag = etag(to_json(&vec!["a"]).as_bytes());
+        let cases = [
+            (tag.clone(), StatusCode::NOT_MODIFIED),
+            (format!("\"other\", W/{}", tag), StatusCode::NOT_MODIFIED),
+            ("*".to_owned(), StatusCode::NOT_MODIFIED),
+            ("\"other\"".to_owned(), StatusCode::OK),
+        ];
+
+        for (condition, status) in cases {
+            let response = conditional_json(
+                &vec!["a"],
+                None,
+                &headers(header::IF_NONE_MATCH, &condition),
+            );
+
+            assert_eq!(response.status(), status, "If-None-Match: {}", condition);
+            assert_eq!(response.headers()[header::ETAG], tag.as_str());
+        }
+    }
+
+    #[test]
+    fn etag_should_take_precedence_over_dates() {
+        let mut headers = headers(header::IF_NONE_MATCH, "\"other\"");
+
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Sun, 18 Oct 2026 13:00:00 GMT"),
+        );
+
+        let response = conditional_json(&vec!["a"], Some(updated()), &headers);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::LAST_MODIFIED],
+            "Sun, 18 Oct 2026 12:00:00 GMT"
+        );
+    }
+
+    #[test]
+    fn if_match_should_use_strong_comparison() {
+        let tag = etag(to_json(&vec!["a"]).as_bytes());
+
+        assert_eq!(check_if_match(&tag, &vec!["a"]), Ok(()));
+        assert_eq!(check_if_match("*", &vec!["a"]), Ok(()));
+        assert!(check_if_match(&format!("W/{}", tag), &vec!["a"]).is_err());
+        assert_eq!(
+            check_if_match(&tag, &vec!["b"]),
+            Err(HandlerError::PreconditionFailed(
+                "The resource has changed since it was read.".to_owned()
+            ))
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use axum::{
+    async_trait,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        FromRequest, FromRequestParts, Path, Request,
+    },
+    http::{header, request::Parts, StatusCode},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+use crate::{
+    models::{AnswerId, FieldError, QuestionId, WebhookId},
+    AppState,
+};
+
+use super::{inner::HandlerError, IDEMPOTENCY_KEY_HEADER};
+
+async fn parse_id<S: Send + Sync>(
+    parts: &mut Parts,
+    state: &S,
+    resource: &str,
+) -> Result<Uuid, HandlerError> {
+    let Path(raw) = Path::<String>::from_request_parts(parts, state)
+        .await
+        .map_err(|rejection| HandlerError::BadRequest(rejection.body_text()))?;
+
+    return Uuid::parse_str(&raw)
+        .map_err(|_| HandlerError::BadRequest(format!("Invalid {} id: {}", resource, raw)));
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for QuestionId {
+    type Rejection = HandlerError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        return Ok(Self(parse_id(parts, state, "question").await?));
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AnswerId {
+    type Rejection = HandlerError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        return Ok(Self(parse_id(parts, state, "answer").await?));
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for WebhookId {
+    type Rejection = HandlerError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        return Ok(Self(parse_id(parts, state, "webhook").await?));
+    }
+}
+
+/// Guards admin endpoints behind `Authorization: Bearer <ADMIN_TOKEN>`. Admin endpoints are
+/// disabled when no token is configured.
+pub struct Admin;
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    return a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0;
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Admin {
+    type Rejection = HandlerError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(expected) = &state.admin_token else {
+            return Err(HandlerError::Forbidden(String::from(
+                "Admin endpoints are disabled.",
+            )));
+        };
+
+        let provided = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        return match provided {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(Self),
+            Some(_) => Err(HandlerError::Forbidden(String::from(
+                "The admin token is not valid.",
+            ))),
+            None => Err(HandlerError::Unauthorized(String::from(
+                "An admin bearer token is required.",
+            ))),
+        };
+    }
+}
+
+/// The optional `Idempotency-Key` of a request, which may be up to 255 visible ASCII characters.
+pub struct IdempotencyKey(pub Option<String>);
+
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IdempotencyKey {
+    type Rejection = HandlerError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(Self(None));
+        };
+
+        return match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH => {
+                Ok(Self(Some(key.to_owned())))
+            }
+            _ => Err(HandlerError::BadRequest(format!(
+                "The Idempotency-Key header must be 1 to {} visible ASCII characters.",
+                MAX_IDEMPOTENCY_KEY_LENGTH
+            ))),
+        };
+    }
+}
+
+/// JSON body extractor that runs the `Validate` rules of the payload and reports every invalid
+/// field at once.
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = HandlerError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state).await?;
+
+        value.validate()?;
+
+        return Ok(Self(value));
+    }
+}
+
+impl From<JsonRejection> for HandlerError {
+    fn from(rejection: JsonRejection) -> Self {
+        let message = rejection.body_text();
+
+        return match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge(message),
+            StatusCode::UNPROCESSABLE_ENTITY => Self::UnprocessableEntity(message, Vec::new()),
+            _ => Self::BadRequest(message),
+        };
+    }
+}
+
+impl From<QueryRejection> for HandlerError {
+    fn from(rejection: QueryRejection) -> Self {
+        return Self::BadRequest(rejection.body_text());
+    }
+}
+
+/// Collects the errors of every field, including those of nested structs. Nested structs are
+/// flattened into the request body, so their fields are reported by their own names.
+fn collect_field_errors(errors: &ValidationErrors, field_errors: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                field_errors.extend(errors.iter().map(|error| FieldError {
+                    field: (*field).to_owned(),
+                    message: error.message.as_ref().unwrap_or(&error.code).to_string(),
+                }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, field_errors),
+            ValidationErrorsKind::List(errors) => {
+                for errors in errors.values() {
+                    collect_field_errors(errors, field_errors);
+                }
+            }
+        }
+    }
+}
+
+/// Lists the errors of every field, ordered by field.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut field_errors = Vec::new();
+
+    collect_field_errors(errors, &mut field_errors);
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+
+    return field_errors;
+}
+
+impl From<ValidationErrors> for HandlerError {
+    fn from(errors: ValidationErrors) -> Self {
+        return Self::UnprocessableEntity(
+            String::from("The request body failed validation."),
+            field_errors(&errors),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        extract::DefaultBodyLimit,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::models::{ProblemDetails, QuestionFields, SelfAnsweredQuestionFields};
+
+    const DESCRIPTION: &str = "A description that is long enough to be accepted.";
+
+    async fn echo_question_id(id: QuestionId) -> String {
+        return id.to_string();
+    }
+
+    async fn echo_question(ValidatedJson(question): ValidatedJson<QuestionFields>) -> String {
+        return question.title;
+    }
+
+    async fn echo_self_answered_question(
+        ValidatedJson(fields): ValidatedJson<SelfAnsweredQuestionFields>,
+    ) -> String {
+        return fields.question.title;
+    }
+
+    async fn echo_idempotency_key(IdempotencyKey(key): IdempotencyKey) -> String {
+        return key.unwrap_or_default();
+    }
+
+    async fn send(uri: &str) -> (StatusCode, Vec<u8>) {
+        return send_request(Request::builder().uri(uri).body(Body::empty()).unwrap()).await;
+    }
+
+    async fn post_question(body: String) -> (StatusCode, Vec<u8>) {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/question")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+
+        return send_request(request).await;
+    }
+
+    async fn send_request(request: Request) -> (StatusCode, Vec<u8>) {
+        let router = Router::new()
+            .route("/question/:id", get(echo_question_id))
+            .route("/question", post(echo_question))
+            .route("/self-answered", post(echo_self_answered_question))
+            .route("/key", get(echo_idempotency_key))
+            .layer(DefaultBodyLimit::max(1024));
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        return (status, body.to_vec());
+    }
+
+    #[tokio::test]
+    async fn question_id_should_be_extracted() {
+        let id = Uuid::new_v4();
+
+        let (status, body) = send(&format!("/question/{}", id)).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, id.to_string().into_bytes());
+    }
+
+    #[tokio::test]
+    async fn malformed_question_id_should_be_rejected() {
+        let (status, body) = send("/question/not-a-uuid").await;
+        let problem: ProblemDetails = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(problem.detail, "Invalid question id: not-a-uuid");
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_should_be_optional_but_not_empty() {
+        let with_key = |key: &str| {
+            return Request::builder()
+                .uri("/key")
+                .header(IDEMPOTENCY_KEY_HEADER, key)
+                .body(Body::empty())
+                .unwrap();
+        };
+
+        assert_eq!(send("/key").await, (StatusCode::OK, Vec::new()));
+        assert_eq!(
+            send_request(with_key("a-key")).await,
+            (StatusCode::OK, b"a-key".to_vec())
+        );
+        assert_eq!(send_request(with_key("")).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            send_request(with_key(&"k".repeat(256))).await.0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn valid_question_should_be_trimmed() {
+        let body = format!(
+            r#"{{"title": "  How do I borrow a value twice?  ", "description": "{}"}}"#,
+            DESCRIPTION
+        );
+
+        let (status, body) = post_question(body).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"How do I borrow a value twice?");
+    }
+
+    #[tokio::test]
+    async fn invalid_question_should_report_every_field() {
+        let (status, body) =
+            post_question(r#"{"title": "short", "description": "   "}"#.to_owned()).await;
+        let problem: ProblemDetails = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            problem.errors,
+            vec![
+                FieldError {
+                    field: "description".to_owned(),
+                    message: "must be between 30 and 30000 characters".to_owned(),
+                },
+                FieldError {
+                    field: "title".to_owned(),
+                    message: "must be between 15 and 150 characters".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn self_answered_question_should_report_question_fields_by_name() {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/self-answered")
+            .header("content-type", "application/json")
+            .body(Body::from(format!(
+                r#"{{"title": "short", "description": "{}", "answer": "  "}}"#,
+                DESCRIPTION
+            )))
+            .unwrap();
+
+        let (status, body) = send_request(request).await;
+        let problem: ProblemDetails = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            problem.errors,
+            vec![
+                FieldError {
+                    field: "answer".to_owned(),
+                    message: "must be between 30 and 30000 characters".to_owned(),
+                },
+                FieldError {
+                    field: "title".to_owned(),
+                    message: "must be between 15 and 150 characters".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn shouting_title_should_be_rejected() {
+        let body = format!(
+            r#"{{"title": "WHY DOES MY CODE NOT COMPILE", "description": "{}"}}"#,
+            DESCRIPTION
+        );
+
+        let (status, body) = post_question(body).await;
+        let problem: ProblemDetails = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem.errors.len(), 1);
+        assert_eq!(problem.errors[0].field, "title");
+    }
+
+    #[tokio::test]
+    async fn oversized_body_should_be_rejected() {
+        let body = format!(
+            r#"{{"title": "How do I borrow a value twice?", "description": "{}"}}"#,
+            "a".repeat(2048)
+        );
+
+        let (status, _) = post_question(body).await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
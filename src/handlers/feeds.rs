@@ -0,0 +1,427 @@
+use std::cmp::Reverse;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::DateTime;
+use tracing::instrument;
+
+use crate::{
+    feeds::{Entry, Feed, FEED_SIZE},
+    models::*,
+    AppState,
+};
+
+use super::{
+    conditional::{etag, http_date, is_not_modified},
+    inner::HandlerError,
+};
+
+#[derive(Clone, Copy)]
+enum Format {
+    Atom,
+    Rss,
+}
+
+impl Format {
+    fn content_type(&self) -> &'static str {
+        return match self {
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Rss => "application/rss+xml; charset=utf-8",
+        };
+    }
+
+    fn extension(&self) -> &'static str {
+        return match self {
+            Self::Atom => "atom",
+            Self::Rss => "rss",
+        };
+    }
+}
+
+/// Responds with the rendered feed along with its validators, or with 304 when the client's
+/// copy is current. The ETag covers the whole feed, so it also tells when entries are removed,
+/// which can move `updated` back.
+fn respond(feed: Feed, format: Format, headers: &HeaderMap) -> Response {
+    let body = match format {
+        Format::Atom => feed.to_atom(),
+        Format::Rss => feed.to_rss(),
+    };
+    let etag = etag(body.as_bytes());
+    let mut validators = HeaderMap::new();
+
+    // Hex digits, quotes and HTTP dates are always valid header characters
+    validators.insert(header::ETAG, HeaderValue::from_str(&etag).unwrap());
+    validators.insert(
+        header::LAST_MODIFIED,
+        HeaderValue::from_str(&http_date(feed.updated)).unwrap(),
+    );
+
+    if is_not_modified(headers, &etag, Some(feed.updated)) {
+        return (StatusCode::NOT_MODIFIED, validators).into_response();
+    }
+
+    return (
+        validators,
+        [(header::CONTENT_TYPE, format.content_type())],
+        body,
+    )
+        .into_response();
+}
+
+async fn questions_feed(state: &AppState, format: Format) -> Result<Feed, HandlerError> {
+    let questions = state
+        .questions_dao
+        .get_latest_questions(FEED_SIZE as i64)
+        .await?;
+
+    return Ok(Feed {
+        title: String::from("Newest questions"),
+        url: format!("{}/questions/feed.{}", state.public_url, format.extension()),
+        link: format!("{}/questions", state.public_url),
+        // An empty feed has never been modified
+        updated: questions
+            .first()
+            .map(|question| question.created_at)
+            .unwrap_or(DateTime::UNIX_EPOCH),
+        entries: questions
+            .into_iter()
+            .map(|question| Entry {
+                id: question.question_uuid.0,
+                link: format!("{}/question/{}", state.public_url, question.question_uuid),
+                title: question.detail.title,
+                published: question.created_at,
+                content_html: question.description_html,
+            })
+            .collect(),
+    });
+}
+
+async fn answers_feed(
+    state: &AppState,
+    id: QuestionId,
+    format: Format,
+) -> Result<Feed, HandlerError> {
+    let QuestionDetail {
+        question,
+        mut answers,
+        ..
+    } = state.questions_dao.get_question(id).await?;
+
+    answers.sort_by_key(|answer| Reverse(answer.created_at));
+    answers.truncate(FEED_SIZE);
+
+    let link = format!("{}/question/{}", state.public_url, id);
+
+    return Ok(Feed {
+        title: format!("Answers to: {}", question.detail.title),
+        url: format!("{}/feed.{}", link, format.extension()),
+        updated: answers
+            .first()
+            .map(|answer| answer.created_at)
+            .unwrap_or(question.created_at),
+        entries: answers
+            .into_iter()
+            .map(|answer| Entry {
+                id: answer.answer_uuid.0,
+                title: format!("Answer to: {}", question.detail.title),
+                link: link.clone(),
+                published: answer.created_at,
+                content_html: answer.content_html,
+            })
+            .collect(),
+        link,
+    });
+}
+
+#[utoipa::path(
+    get,
+    path = "/questions/feed.atom",
+    tag = "feeds",
+    responses(
+        (
+            status = 200,
+            description = "Atom feed of the newest questions",
+            body = String,
+            content_type = "application/atom+xml"
+        ),
+        (
+            status = 304,
+            description = "Not modified since the `ETag` or, without one, `If-Modified-Since`"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn read_questions_atom(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let feed = questions_feed(&state, Format::Atom).await?;
+
+    return Ok(respond(feed, Format::Atom, &headers));
+}
+
+#[utoipa::path(
+    get,
+    path = "/questions/feed.rss",
+    tag = "feeds",
+    responses(
+        (
+            status = 200,
+            description = "RSS feed of the newest questions",
+            body = String,
+            content_type = "application/rss+xml"
+        ),
+        (
+            status = 304,
+            description = "Not modified since the `ETag` or, without one, `If-Modified-Since`"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn read_questions_rss(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let feed = questions_feed(&state, Format::Rss).await?;
+
+    return Ok(respond(feed, Format::Rss, &headers));
+}
+
+#[utoipa::path(
+    get,
+    path = "/question/{id}/feed.atom",
+    tag = "feeds",
+    params(("id" = Uuid, Path, description = "ID of the answered question")),
+    responses(
+        (
+            status = 200,
+            description = "Atom feed of the newest answers to the question",
+            body = String,
+            content_type = "application/atom+xml"
+        ),
+        (
+            status = 304,
+            description = "Not modified since the `ETag` or, without one, `If-Modified-Since`"
+        ),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn read_answers_atom(
+    State(state): State<AppState>,
+    id: QuestionId,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let feed = answers_feed(&state, id, Format::Atom).await?;
+
+    return Ok(respond(feed, Format::Atom, &headers));
+}
+
+#[utoipa::path(
+    get,
+    path = "/question/{id}/feed.rss",
+    tag = "feeds",
+    params(("id" = Uuid, Path, description = "ID of the answered question")),
+    responses(
+        (
+            status = 200,
+            description = "RSS feed of the newest answers to the question",
+            body = String,
+            content_type = "application/rss+xml"
+        ),
+        (
+            status = 304,
+            description = "Not modified since the `ETag` or, without one, `If-Modified-Since`"
+        ),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn read_answers_rss(
+    State(state): State<AppState>,
+    id: QuestionId,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let feed = answers_feed(&state, id, Format::Rss).await?;
+
+    return Ok(respond(feed, Format::Rss, &headers));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+    use chrono::Utc;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        activity::ActivityFeed,
+        persistance::{
+            answers_dao, backup_dao, cached_dao::DaoCache, questions_dao, unit_of_work,
+            webhooks_dao,
+        },
+        views::ViewCounter,
+    };
+
+    fn state(pool: PgPool) -> AppState {
+        return AppState {
+            questions_dao: Arc::new(questions_dao::DAO::new(pool.clone())),
+            answers_dao: Arc::new(answers_dao::DAO::new(pool.clone())),
+            webhooks_dao: Arc::new(webhooks_dao::DAO::new(pool.clone())),
+            backup_dao: Arc::new(backup_dao::DAO::new(pool.clone())),
+            unit_of_work_dao: Arc::new(unit_of_work::DAO::new(
+                pool.clone(),
+                Arc::new(DaoCache::new(NonZeroUsize::MIN, Duration::ZERO)),
+            )),
+            database: pool,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            view_counter: Arc::new(ViewCounter::new(Duration::from_secs(60))),
+            activity: ActivityFeed::new(),
+            public_url: "http://localhost".to_owned(),
+            admin_token: None,
+        };
+    }
+
+    fn headers(if_modified_since: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(if_modified_since).unwrap(),
+        );
+
+        return headers;
+    }
+
+    fn updated() -> DateTime<Utc> {
+        return DateTime::parse_from_rfc3339("2026-10-18T12:00:00.250Z")
+            .unwrap()
+            .to_utc();
+    }
+
+    #[test]
+    fn fresh_feed_should_not_be_sent() {
+        let feed = Feed {
+            title: "Newest questions".to_owned(),
+            url: "http://localhost/questions/feed.atom".to_owned(),
+            link: "http://localhost/questions".to_owned(),
+            updated: updated(),
+            entries: vec![],
+        };
+
+        let response = respond(
+            feed,
+            Format::Atom,
+            &headers("Sun, 18 Oct 2026 12:00:00 GMT"),
+        );
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers()[header::LAST_MODIFIED],
+            "Sun, 18 Oct 2026 12:00:00 GMT"
+        );
+    }
+
+    #[sqlx::test]
+    async fn feed_should_change_when_newest_question_is_deleted(pool: PgPool) {
+        let state = state(pool);
+
+        for title in ["First", "Second"] {
+            state
+                .questions_dao
+                .create_question(QuestionFields {
+                    title: title.to_owned(),
+                    description: "Description".to_owned(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let feed = questions_feed(&state, Format::Atom).await.unwrap();
+        let newest = feed.entries[0].id;
+        let response = respond(feed, Format::Atom, &HeaderMap::new());
+        let mut conditions = HeaderMap::new();
+
+        conditions.insert(
+            header::IF_NONE_MATCH,
+            response.headers()[header::ETAG].clone(),
+        );
+        conditions.insert(
+            header::IF_MODIFIED_SINCE,
+            response.headers()[header::LAST_MODIFIED].clone(),
+        );
+
+        let unchanged = questions_feed(&state, Format::Atom).await.unwrap();
+
+        assert_eq!(
+            respond(unchanged, Format::Atom, &conditions).status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        state
+            .questions_dao
+            .delete_question(QuestionId(newest))
+            .await
+            .unwrap();
+
+        // The feed now looks older than the client's copy, so only the ETag tells it changed
+        let changed = questions_feed(&state, Format::Atom).await.unwrap();
+
+        assert_eq!(changed.entries.len(), 1);
+        assert_eq!(
+            respond(changed, Format::Atom, &conditions).status(),
+            StatusCode::OK
+        );
+    }
+}
@@ -1,99 +1,443 @@
-use uuid::Uuid;
-
-use crate::persistance::{answers_dao::AnswerDAO, questions_dao::QuestionDAO};
+use std::net::IpAddr;
 
-use super::{Answer, AnswerFields, DBError, Question, QuestionFields};
+use metrics::counter;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::error;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    activity::{Activity, ActivityFeed},
+    persistance::{
+        answers_dao::AnswerDAO,
+        backup_dao::BackupDAO,
+        idempotency_dao::Claim,
+        questions_dao::QuestionDAO,
+        unit_of_work::{UnitOfWork, UnitOfWorkDAO},
+        webhooks_dao::WebhookDAO,
+    },
+    views::ViewCounter,
+    webhooks,
+};
+
+use super::{
+    conditional::{check_if_match, to_json},
+    extractors::field_errors,
+    Answer, AnswerFields, AnswerId, DBError, ExportRecord, FieldError, ImportSummary, Question,
+    QuestionDetail, QuestionFields, QuestionId, SelfAnsweredQuestionFields, Webhook,
+    WebhookDelivery, WebhookFields, WebhookId,
+};
 
 #[derive(Debug, PartialEq)]
 pub enum HandlerError {
     BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    PreconditionFailed(String),
+    PayloadTooLarge(String),
+    UnprocessableEntity(String, Vec<FieldError>),
     InternalError(String),
 }
 
 impl HandlerError {
-    fn default_internal_error() -> Self {
+    fn default_internal_error(error: DBError) -> Self {
+        error!(error = ?error, "Request failed with a database error");
+
         return Self::InternalError(String::from("Something went wrong! Please try again."));
     }
 }
 
+impl From<DBError> for HandlerError {
+    fn from(error: DBError) -> Self {
+        return match error {
+            DBError::NotFound(message) | DBError::ForeignKeyViolation(message) => {
+                Self::NotFound(message)
+            }
+            DBError::UniqueViolation(message) | DBError::SerializationFailure(message) => {
+                Self::Conflict(message)
+            }
+            DBError::CheckViolation(message) => Self::UnprocessableEntity(message, Vec::new()),
+            error @ DBError::Other(_) => Self::default_internal_error(error),
+        };
+    }
+}
+
+/// Identifies a request by its endpoint and parsed body, so retries match however their JSON
+/// is formatted.
+fn fingerprint<T: Serialize>(endpoint: &str, request: &T) -> String {
+    let body = to_json(request);
+    let mut hasher = Sha256::new();
+
+    hasher.update(endpoint.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+
+    return hex::encode(hasher.finalize());
+}
+
+/// What to do with a request that may carry an idempotency key.
+enum Idempotency<'a, T> {
+    /// Run the request, completing the claimed key and token if there is one.
+    Run(Option<(&'a str, Uuid)>),
+    /// Reply with the response of the request that completed the key.
+    Replay(T),
+}
+
+/// Claims the idempotency key of a request in the unit of work that runs it. The key is only
+/// stored along with the response, so a request that fails leaves it free for a retry.
+async fn claim_key<'a, T: DeserializeOwned>(
+    work: &(dyn UnitOfWork + Send + Sync),
+    key: Option<&'a str>,
+    fingerprint: &str,
+) -> Result<Idempotency<'a, T>, HandlerError> {
+    let Some(key) = key else {
+        return Ok(Idempotency::Run(None));
+    };
+
+    return match work.idempotency().claim(key, fingerprint).await? {
+        Claim::Claimed(token) => Ok(Idempotency::Run(Some((key, token)))),
+        Claim::Completed(response) => {
+            let value = serde_json::from_value(response).map_err(|error| {
+                return HandlerError::default_internal_error(DBError::Other(Box::new(error)));
+            })?;
+
+            Ok(Idempotency::Replay(value))
+        }
+        Claim::Mismatch => Err(HandlerError::UnprocessableEntity(
+            String::from("The idempotency key was already used for a different request."),
+            Vec::new(),
+        )),
+    };
+}
+
+/// Stores the response of a request to replay for the key it claimed.
+async fn complete_key<T: Serialize>(
+    work: &(dyn UnitOfWork + Send + Sync),
+    claimed: Option<(&str, Uuid)>,
+    response: &T,
+) -> Result<(), HandlerError> {
+    if let Some((key, token)) = claimed {
+        work.idempotency()
+            .complete(key, token, &to_json(response))
+            .await?;
+    }
+
+    return Ok(());
+}
+
+/// Queues the webhook deliveries of an activity in the unit of work that stores it, so they are
+/// queued if and only if the change is committed.
+async fn queue_deliveries(
+    work: &(dyn UnitOfWork + Send + Sync),
+    activity: &Activity,
+) -> Result<(), HandlerError> {
+    if let Some((event, payload)) = webhooks::payload(activity.clone()) {
+        work.webhooks().enqueue_deliveries(event, payload).await?;
+    }
+
+    return Ok(());
+}
+
+/// Creates the question once per idempotency key, and replays it to retries with the same key.
+/// Returns the question along with whether it was replayed.
 pub async fn create_question(
     question: QuestionFields,
+    key: Option<&str>,
+    dao: &(dyn UnitOfWorkDAO + Send + Sync),
+    activity: &ActivityFeed,
+) -> Result<(Question, bool), HandlerError> {
+    let fingerprint = fingerprint("POST /question", &question);
+    let work = dao.begin().await?;
+
+    let claimed = match claim_key(work.as_ref(), key, &fingerprint).await? {
+        Idempotency::Run(claimed) => claimed,
+        Idempotency::Replay(question) => return Ok((question, true)),
+    };
+
+    let question = work.questions().create_question(question).await?;
+    let created = Activity::QuestionCreated(question.clone());
+
+    queue_deliveries(work.as_ref(), &created).await?;
+    complete_key(work.as_ref(), claimed, &question).await?;
+    work.commit().await?;
+
+    counter!("questions_created_total").increment(1);
+    activity.publish(created);
+
+    return Ok((question, false));
+}
+
+pub async fn read_question(
+    id: QuestionId,
+    viewer: Option<IpAddr>,
     dao: &(dyn QuestionDAO + Send + Sync),
-) -> Result<Question, HandlerError> {
-    return Ok(dao
-        .create_question(question)
-        .await
-        .map_err(|_| HandlerError::default_internal_error())?);
+    views: &ViewCounter,
+) -> Result<QuestionDetail, HandlerError> {
+    let mut question = dao.get_question(id).await?;
+
+    if let Some(viewer) = viewer {
+        views.record(id, viewer);
+    }
+
+    // Include views that have not been flushed to the database yet
+    question.view_count += views.pending(id);
+
+    return Ok(question);
 }
 
 pub async fn read_questions(
     dao: &(dyn QuestionDAO + Send + Sync),
 ) -> Result<Vec<Question>, HandlerError> {
-    return Ok(dao
-        .get_questions()
-        .await
-        .map_err(|_| HandlerError::default_internal_error())?);
+    return Ok(dao.get_questions().await?);
 }
 
+/// Deletes the question, if given only when `condition` names its current content. The question
+/// stays locked from the check until the deletion is committed.
 pub async fn delete_question(
-    id: Uuid,
-    dao: &(dyn QuestionDAO + Send + Sync),
+    id: QuestionId,
+    condition: Option<&str>,
+    dao: &(dyn UnitOfWorkDAO + Send + Sync),
+    activity: &ActivityFeed,
 ) -> Result<(), HandlerError> {
-    return Ok(dao.delete_question(id).await.map_err(|e| {
-        return match e {
-            DBError::InvalidUUID(message) => HandlerError::BadRequest(message),
-            DBError::Other(_) => HandlerError::default_internal_error(),
-        };
-    })?);
+    let work = dao.begin().await?;
+
+    if let Some(condition) = condition {
+        work.questions().lock_question(id).await?;
+
+        let current = work.questions().get_question(id).await?;
+
+        check_if_match(condition, &current.content())?;
+    }
+
+    work.questions().delete_question(id).await?;
+
+    let deleted = Activity::QuestionDeleted(id);
+
+    queue_deliveries(work.as_ref(), &deleted).await?;
+    work.commit().await?;
+    activity.publish(deleted);
+
+    return Ok(());
 }
 
+/// Creates the answer once per idempotency key, and replays it to retries with the same key.
+/// Returns the answer along with whether it was replayed.
 pub async fn create_answer(
     answer: AnswerFields,
-    dao: &(dyn AnswerDAO + Send + Sync),
-) -> Result<Answer, HandlerError> {
-    return Ok(dao.create_answer(answer).await.map_err(|e| {
-        return match e {
-            DBError::InvalidUUID(message) => HandlerError::BadRequest(message),
-            DBError::Other(_) => HandlerError::default_internal_error(),
-        };
-    })?);
+    key: Option<&str>,
+    dao: &(dyn UnitOfWorkDAO + Send + Sync),
+    activity: &ActivityFeed,
+) -> Result<(Answer, bool), HandlerError> {
+    let fingerprint = fingerprint("POST /answer", &answer);
+    let work = dao.begin().await?;
+
+    let claimed = match claim_key(work.as_ref(), key, &fingerprint).await? {
+        Idempotency::Run(claimed) => claimed,
+        Idempotency::Replay(answer) => return Ok((answer, true)),
+    };
+
+    let answer = work.answers().create_answer(answer).await?;
+    let created = Activity::AnswerCreated(answer.clone());
+
+    queue_deliveries(work.as_ref(), &created).await?;
+    complete_key(work.as_ref(), claimed, &answer).await?;
+    work.commit().await?;
+
+    counter!("answers_created_total").increment(1);
+    activity.publish(created);
+
+    return Ok((answer, false));
+}
+
+/// Stores a question and its asker's answer in one unit of work, so neither is visible without
+/// the other.
+pub async fn create_self_answered_question(
+    fields: SelfAnsweredQuestionFields,
+    dao: &(dyn UnitOfWorkDAO + Send + Sync),
+    activity: &ActivityFeed,
+) -> Result<QuestionDetail, HandlerError> {
+    let work = dao.begin().await?;
+
+    let question = work.questions().create_question(fields.question).await?;
+    let answer = work
+        .answers()
+        .create_answer(AnswerFields {
+            question_uuid: question.question_uuid,
+            content: fields.answer,
+        })
+        .await?;
+    let created = [
+        Activity::QuestionCreated(question.clone()),
+        Activity::AnswerCreated(answer.clone()),
+    ];
+
+    for created in &created {
+        queue_deliveries(work.as_ref(), created).await?;
+    }
+
+    work.commit().await?;
+
+    counter!("questions_created_total").increment(1);
+    counter!("answers_created_total").increment(1);
+
+    for created in created {
+        activity.publish(created);
+    }
+
+    return Ok(QuestionDetail {
+        question,
+        answers: vec![answer],
+        answer_count: 1,
+        view_count: 0,
+    });
 }
 
 pub async fn read_answers(
-    question_id: Uuid,
+    question_id: QuestionId,
     dao: &(dyn AnswerDAO + Send + Sync),
 ) -> Result<Vec<Answer>, HandlerError> {
-    return Ok(dao.get_answers(question_id).await.map_err(|e| {
-        return match e {
-            DBError::InvalidUUID(message) => HandlerError::BadRequest(message),
-            DBError::Other(_) => HandlerError::default_internal_error(),
-        };
-    })?);
+    return Ok(dao.get_answers(question_id).await?);
 }
 
-pub async fn delete_answer(
-    id: Uuid,
+pub async fn read_answer(
+    id: AnswerId,
     dao: &(dyn AnswerDAO + Send + Sync),
+) -> Result<Answer, HandlerError> {
+    return Ok(dao.get_answer(id).await?);
+}
+
+/// Deletes the answer, if given only when `condition` names its current representation. The
+/// answer stays locked from the check until the deletion is committed.
+pub async fn delete_answer(
+    id: AnswerId,
+    condition: Option<&str>,
+    dao: &(dyn UnitOfWorkDAO + Send + Sync),
+    activity: &ActivityFeed,
 ) -> Result<(), HandlerError> {
-    return Ok(dao.delete_answer(id).await.map_err(|e| {
-        return match e {
-            DBError::InvalidUUID(message) => HandlerError::BadRequest(message),
-            DBError::Other(_) => HandlerError::default_internal_error(),
-        };
-    })?);
+    let work = dao.begin().await?;
+
+    if let Some(condition) = condition {
+        work.answers().lock_answer(id).await?;
+
+        let current = read_answer(id, work.answers()).await?;
+
+        check_if_match(condition, &current)?;
+    }
+
+    work.answers().delete_answer(id).await?;
+
+    let deleted = Activity::AnswerDeleted(id);
+
+    queue_deliveries(work.as_ref(), &deleted).await?;
+    work.commit().await?;
+    activity.publish(deleted);
+
+    return Ok(());
+}
+
+pub async fn create_webhook(
+    webhook: WebhookFields,
+    dao: &(dyn WebhookDAO + Send + Sync),
+) -> Result<Webhook, HandlerError> {
+    return Ok(dao.create_webhook(webhook).await?);
+}
+
+pub async fn read_webhooks(
+    dao: &(dyn WebhookDAO + Send + Sync),
+) -> Result<Vec<Webhook>, HandlerError> {
+    return Ok(dao.get_webhooks().await?);
+}
+
+pub async fn delete_webhook(
+    id: WebhookId,
+    dao: &(dyn WebhookDAO + Send + Sync),
+) -> Result<(), HandlerError> {
+    return Ok(dao.delete_webhook(id).await?);
+}
+
+pub async fn read_deliveries(
+    id: WebhookId,
+    dao: &(dyn WebhookDAO + Send + Sync),
+) -> Result<Vec<WebhookDelivery>, HandlerError> {
+    return Ok(dao.get_deliveries(id).await?);
+}
+
+/// Imports a JSON Lines export. Blank lines are skipped; any invalid line, or record that fails
+/// validation, rejects the import.
+pub async fn import_records(
+    lines: &str,
+    dry_run: bool,
+    dao: &(dyn BackupDAO + Send + Sync),
+) -> Result<ImportSummary, HandlerError> {
+    let records = lines
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let record: ExportRecord = serde_json::from_str(line).map_err(|error| {
+                HandlerError::UnprocessableEntity(
+                    format!("Line {} is not a valid record: {}", index + 1, error),
+                    Vec::new(),
+                )
+            })?;
+
+            record.validate().map_err(|errors| {
+                HandlerError::UnprocessableEntity(
+                    format!("Line {} failed validation.", index + 1),
+                    field_errors(&errors),
+                )
+            })?;
+
+            return Ok(record);
+        })
+        .collect::<Result<Vec<ExportRecord>, HandlerError>>()?;
+
+    return match dao.import(records, dry_run).await {
+        Ok(summary) => Ok(summary),
+        // A dangling reference is a problem with the import, not a missing resource
+        Err(DBError::ForeignKeyViolation(message)) => {
+            Err(HandlerError::UnprocessableEntity(message, Vec::new()))
+        }
+        Err(error) => Err(error.into()),
+    };
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::{
+        collections::HashMap,
+        net::Ipv4Addr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
     use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
     use sqlx::Error;
     use tokio::sync::Mutex;
+    use tokio_stream::StreamExt;
+    use uuid::Uuid;
+
+    use crate::{
+        handlers::conditional::etag,
+        models::{DueDelivery, WebhookEvent},
+        persistance::{
+            backup_dao::RecordStream, idempotency_dao::IdempotencyDAO, unit_of_work::UnitOfWork,
+        },
+    };
 
     struct QuestionsDaoMock {
         create_question_response: Mutex<Option<Result<Question, DBError>>>,
         delete_question_response: Mutex<Option<Result<(), DBError>>>,
+        get_question_response: Mutex<Option<Result<QuestionDetail, DBError>>>,
         get_questions_response: Mutex<Option<Result<Vec<Question>, DBError>>>,
     }
 
@@ -102,6 +446,7 @@ mod tests {
             QuestionsDaoMock {
                 create_question_response: Mutex::new(None),
                 delete_question_response: Mutex::new(None),
+                get_question_response: Mutex::new(None),
                 get_questions_response: Mutex::new(None),
             }
         }
@@ -111,6 +456,9 @@ mod tests {
         pub fn mock_delete_question(&mut self, response: Result<(), DBError>) {
             self.delete_question_response = Mutex::new(Some(response));
         }
+        pub fn mock_get_question(&mut self, response: Result<QuestionDetail, DBError>) {
+            self.get_question_response = Mutex::new(Some(response));
+        }
         pub fn mock_get_questions(&mut self, response: Result<Vec<Question>, DBError>) {
             self.get_questions_response = Mutex::new(Some(response));
         }
@@ -125,13 +473,23 @@ mod tests {
                 .take()
                 .expect("create_question_response should not be None.")
         }
-        async fn delete_question(&self, _: Uuid) -> Result<(), DBError> {
+        async fn delete_question(&self, _: QuestionId) -> Result<(), DBError> {
             self.delete_question_response
                 .lock()
                 .await
                 .take()
                 .expect("delete_question_response should not be None.")
         }
+        async fn get_question(&self, _: QuestionId) -> Result<QuestionDetail, DBError> {
+            self.get_question_response
+                .lock()
+                .await
+                .take()
+                .expect("get_question_response should not be None.")
+        }
+        async fn lock_question(&self, _: QuestionId) -> Result<(), DBError> {
+            Ok(())
+        }
         async fn get_questions(&self) -> Result<Vec<Question>, DBError> {
             self.get_questions_response
                 .lock()
@@ -139,11 +497,21 @@ mod tests {
                 .take()
                 .expect("get_questions_response should not be None.")
         }
+        async fn get_latest_questions(&self, _: i64) -> Result<Vec<Question>, DBError> {
+            Ok(Vec::new())
+        }
+        async fn question_exists(&self, _: QuestionId) -> Result<bool, DBError> {
+            Ok(true)
+        }
+        async fn add_views(&self, _: HashMap<QuestionId, i64>) -> Result<(), DBError> {
+            Ok(())
+        }
     }
 
     struct AnswersDaoMock {
         create_answer_response: Mutex<Option<Result<Answer, DBError>>>,
         delete_answer_response: Mutex<Option<Result<(), DBError>>>,
+        get_answer_response: Mutex<Option<Result<Answer, DBError>>>,
         get_answers_response: Mutex<Option<Result<Vec<Answer>, DBError>>>,
     }
 
@@ -152,6 +520,7 @@ mod tests {
             AnswersDaoMock {
                 create_answer_response: Mutex::new(None),
                 delete_answer_response: Mutex::new(None),
+                get_answer_response: Mutex::new(None),
                 get_answers_response: Mutex::new(None),
             }
         }
@@ -161,6 +530,9 @@ mod tests {
         pub fn mock_delete_answer(&mut self, response: Result<(), DBError>) {
             self.delete_answer_response = Mutex::new(Some(response));
         }
+        pub fn mock_get_answer(&mut self, response: Result<Answer, DBError>) {
+            self.get_answer_response = Mutex::new(Some(response));
+        }
         pub fn mock_get_answers(&mut self, response: Result<Vec<Answer>, DBError>) {
             self.get_answers_response = Mutex::new(Some(response));
         }
@@ -175,14 +547,24 @@ mod tests {
                 .take()
                 .expect("create_answer_response should not be None.")
         }
-        async fn delete_answer(&self, _: Uuid) -> Result<(), DBError> {
+        async fn delete_answer(&self, _: AnswerId) -> Result<(), DBError> {
             self.delete_answer_response
                 .lock()
                 .await
                 .take()
                 .expect("delete_answer_response should not be None.")
         }
-        async fn get_answers(&self, _: Uuid) -> Result<Vec<Answer>, DBError> {
+        async fn lock_answer(&self, _: AnswerId) -> Result<(), DBError> {
+            Ok(())
+        }
+        async fn get_answer(&self, _: AnswerId) -> Result<Answer, DBError> {
+            self.get_answer_response
+                .lock()
+                .await
+                .take()
+                .expect("get_answer_response should not be None.")
+        }
+        async fn get_answers(&self, _: QuestionId) -> Result<Vec<Answer>, DBError> {
             self.get_answers_response
                 .lock()
                 .await
@@ -191,6 +573,239 @@ mod tests {
         }
     }
 
+    struct WebhooksDaoMock {
+        create_webhook_response: Mutex<Option<Result<Webhook, DBError>>>,
+        delete_webhook_response: Mutex<Option<Result<(), DBError>>>,
+        get_deliveries_response: Mutex<Option<Result<Vec<WebhookDelivery>, DBError>>>,
+        enqueued: Arc<Mutex<Vec<WebhookEvent>>>,
+    }
+
+    impl WebhooksDaoMock {
+        pub fn new() -> Self {
+            WebhooksDaoMock {
+                create_webhook_response: Mutex::new(None),
+                delete_webhook_response: Mutex::new(None),
+                get_deliveries_response: Mutex::new(None),
+                enqueued: Arc::default(),
+            }
+        }
+        pub fn mock_create_webhook(&mut self, response: Result<Webhook, DBError>) {
+            self.create_webhook_response = Mutex::new(Some(response));
+        }
+        pub fn mock_delete_webhook(&mut self, response: Result<(), DBError>) {
+            self.delete_webhook_response = Mutex::new(Some(response));
+        }
+        pub fn mock_get_deliveries(&mut self, response: Result<Vec<WebhookDelivery>, DBError>) {
+            self.get_deliveries_response = Mutex::new(Some(response));
+        }
+    }
+
+    #[async_trait]
+    impl WebhookDAO for WebhooksDaoMock {
+        async fn create_webhook(&self, _: WebhookFields) -> Result<Webhook, DBError> {
+            self.create_webhook_response
+                .lock()
+                .await
+                .take()
+                .expect("create_webhook_response should not be None.")
+        }
+        async fn delete_webhook(&self, _: WebhookId) -> Result<(), DBError> {
+            self.delete_webhook_response
+                .lock()
+                .await
+                .take()
+                .expect("delete_webhook_response should not be None.")
+        }
+        async fn get_webhooks(&self) -> Result<Vec<Webhook>, DBError> {
+            Ok(Vec::new())
+        }
+        async fn get_deliveries(&self, _: WebhookId) -> Result<Vec<WebhookDelivery>, DBError> {
+            self.get_deliveries_response
+                .lock()
+                .await
+                .take()
+                .expect("get_deliveries_response should not be None.")
+        }
+        async fn enqueue_deliveries(
+            &self,
+            event: WebhookEvent,
+            _: serde_json::Value,
+        ) -> Result<u64, DBError> {
+            self.enqueued.lock().await.push(event);
+            Ok(1)
+        }
+        async fn claim_due_deliveries(&self, _: i64) -> Result<Vec<DueDelivery>, DBError> {
+            Ok(Vec::new())
+        }
+        async fn mark_delivered(&self, _: Uuid, _: i32) -> Result<(), DBError> {
+            Ok(())
+        }
+        async fn mark_failed(
+            &self,
+            _: Uuid,
+            _: Option<i32>,
+            _: String,
+            _: Option<DateTime<Utc>>,
+        ) -> Result<(), DBError> {
+            Ok(())
+        }
+    }
+
+    struct BackupDaoMock {
+        import_response: Mutex<Option<Result<ImportSummary, DBError>>>,
+        imported: Mutex<Vec<ExportRecord>>,
+    }
+
+    impl BackupDaoMock {
+        pub fn new() -> Self {
+            BackupDaoMock {
+                import_response: Mutex::new(None),
+                imported: Mutex::new(Vec::new()),
+            }
+        }
+        pub fn mock_import(&mut self, response: Result<ImportSummary, DBError>) {
+            self.import_response = Mutex::new(Some(response));
+        }
+    }
+
+    #[async_trait]
+    impl BackupDAO for BackupDaoMock {
+        fn export(&self) -> RecordStream {
+            Box::pin(tokio_stream::empty())
+        }
+        async fn import(
+            &self,
+            records: Vec<ExportRecord>,
+            _: bool,
+        ) -> Result<ImportSummary, DBError> {
+            *self.imported.lock().await = records;
+
+            self.import_response
+                .lock()
+                .await
+                .take()
+                .expect("import_response should not be None.")
+        }
+        async fn merge(&self, _: Vec<ExportRecord>) -> Result<ImportSummary, DBError> {
+            Ok(ImportSummary {
+                questions: 0,
+                answers: 0,
+                dry_run: false,
+            })
+        }
+    }
+
+    struct IdempotencyDaoMock {
+        claim_response: Mutex<Option<Result<Claim, DBError>>>,
+        completed: Arc<Mutex<Option<String>>>,
+    }
+
+    impl IdempotencyDaoMock {
+        pub fn new() -> Self {
+            IdempotencyDaoMock {
+                claim_response: Mutex::new(None),
+                completed: Arc::new(Mutex::new(None)),
+            }
+        }
+        pub fn mock_claim(&mut self, response: Result<Claim, DBError>) {
+            self.claim_response = Mutex::new(Some(response));
+        }
+    }
+
+    #[async_trait]
+    impl IdempotencyDAO for IdempotencyDaoMock {
+        async fn claim(&self, _: &str, _: &str) -> Result<Claim, DBError> {
+            self.claim_response
+                .lock()
+                .await
+                .take()
+                .expect("claim_response should not be None.")
+        }
+        async fn complete(&self, _: &str, _: Uuid, response: &str) -> Result<(), DBError> {
+            *self.completed.lock().await = Some(response.to_owned());
+            Ok(())
+        }
+        async fn delete_expired(&self) -> Result<u64, DBError> {
+            Ok(0)
+        }
+    }
+
+    struct UnitOfWorkMock {
+        questions: QuestionsDaoMock,
+        answers: AnswersDaoMock,
+        webhooks: WebhooksDaoMock,
+        idempotency: IdempotencyDaoMock,
+        committed: Arc<AtomicBool>,
+    }
+
+    impl UnitOfWorkMock {
+        pub fn new(questions: QuestionsDaoMock, answers: AnswersDaoMock) -> Self {
+            UnitOfWorkMock {
+                questions,
+                answers,
+                webhooks: WebhooksDaoMock::new(),
+                idempotency: IdempotencyDaoMock::new(),
+                committed: Arc::new(AtomicBool::new(false)),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl UnitOfWork for UnitOfWorkMock {
+        fn questions(&self) -> &(dyn QuestionDAO + Send + Sync) {
+            &self.questions
+        }
+        fn answers(&self) -> &(dyn AnswerDAO + Send + Sync) {
+            &self.answers
+        }
+        fn webhooks(&self) -> &(dyn WebhookDAO + Send + Sync) {
+            &self.webhooks
+        }
+        fn idempotency(&self) -> &(dyn IdempotencyDAO + Send + Sync) {
+            &self.idempotency
+        }
+        async fn commit(self: Box<Self>) -> Result<(), DBError> {
+            self.committed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    struct UnitOfWorkDaoMock {
+        begin_response: Mutex<Option<Result<Box<dyn UnitOfWork + Send + Sync>, DBError>>>,
+    }
+
+    impl UnitOfWorkDaoMock {
+        /// Begins a unit of work of the given DAOs, and returns whether it was committed.
+        pub fn new(
+            questions: QuestionsDaoMock,
+            answers: AnswersDaoMock,
+        ) -> (Self, Arc<AtomicBool>) {
+            return Self::of(UnitOfWorkMock::new(questions, answers));
+        }
+        /// Begins the given unit of work, and returns whether it was committed.
+        pub fn of(work: UnitOfWorkMock) -> (Self, Arc<AtomicBool>) {
+            let committed = work.committed.clone();
+
+            return (
+                UnitOfWorkDaoMock {
+                    begin_response: Mutex::new(Some(Ok(Box::new(work)))),
+                },
+                committed,
+            );
+        }
+    }
+
+    #[async_trait]
+    impl UnitOfWorkDAO for UnitOfWorkDaoMock {
+        async fn begin(&self) -> Result<Box<dyn UnitOfWork + Send + Sync>, DBError> {
+            self.begin_response
+                .lock()
+                .await
+                .take()
+                .expect("begin_response should not be None.")
+        }
+    }
+
     #[tokio::test]
     async fn create_question_should_return_question() {
         let question = QuestionFields {
@@ -199,11 +814,12 @@ mod tests {
         };
 
         let question_detail = Question {
-            question_uuid: Uuid::new_v4(),
+            question_uuid: QuestionId(Uuid::new_v4()),
             detail: QuestionFields {
                 title: question.title.clone(),
                 description: question.description.clone(),
             },
+            description_html: "<p>test description</p>\n".to_owned(),
             created_at: chrono::offset::Utc::now(),
         };
 
@@ -211,12 +827,40 @@ mod tests {
 
         questions_dao.mock_create_question(Ok(question_detail.clone()));
 
-        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
 
-        let result = create_question(question, questions_dao.as_ref()).await;
+        let result = create_question(question, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), question_detail);
+        assert_eq!(result.unwrap(), (question_detail, false));
+    }
+
+    #[tokio::test]
+    async fn create_question_should_queue_deliveries_in_its_unit_of_work() {
+        let question = QuestionFields {
+            title: "test title".to_owned(),
+            description: "test description".to_owned(),
+        };
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_create_question(Ok(Question {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            detail: question.clone(),
+            description_html: "<p>test description</p>\n".to_owned(),
+            created_at: chrono::offset::Utc::now(),
+        }));
+
+        let work = UnitOfWorkMock::new(questions_dao, AnswersDaoMock::new());
+        let enqueued = work.webhooks.enqueued.clone();
+        let (dao, committed) = UnitOfWorkDaoMock::of(work);
+
+        create_question(question, None, &dao, &ActivityFeed::new())
+            .await
+            .unwrap();
+
+        assert_eq!(*enqueued.lock().await, vec![WebhookEvent::QuestionCreated]);
+        assert!(committed.load(Ordering::SeqCst));
     }
 
     #[tokio::test]
@@ -228,11 +872,11 @@ mod tests {
 
         let mut questions_dao = QuestionsDaoMock::new();
 
-        questions_dao.mock_create_question(Err(DBError::InvalidUUID("test".to_owned())));
+        questions_dao.mock_create_question(Err(DBError::Other(Box::new(Error::PoolTimedOut))));
 
-        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
 
-        let result = create_question(question, questions_dao.as_ref()).await;
+        let result = create_question(question, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_err());
         assert!(
@@ -241,14 +885,102 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn read_question_should_return_question_with_answers() {
+        let question_uuid = QuestionId(Uuid::new_v4());
+
+        let question_detail = QuestionDetail {
+            question: Question {
+                question_uuid,
+                detail: QuestionFields {
+                    title: "test title".to_owned(),
+                    description: "test description".to_owned(),
+                },
+                description_html: "<p>test description</p>\n".to_owned(),
+                created_at: chrono::offset::Utc::now(),
+            },
+            answers: vec![Answer::new(AnswerFields {
+                question_uuid,
+                content: "test content".to_owned(),
+            })],
+            answer_count: 1,
+            view_count: 3,
+        };
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_get_question(Ok(question_detail.clone()));
+
+        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+        let views = ViewCounter::new(Duration::from_secs(60));
+
+        let result = read_question(question_uuid, None, questions_dao.as_ref(), &views).await;
+
+        assert_eq!(result, Ok(question_detail));
+    }
+
+    #[tokio::test]
+    async fn read_question_should_count_unflushed_view() {
+        let question_uuid = QuestionId(Uuid::new_v4());
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_get_question(Ok(QuestionDetail {
+            question: Question {
+                question_uuid,
+                detail: QuestionFields {
+                    title: "test title".to_owned(),
+                    description: "test description".to_owned(),
+                },
+                description_html: "<p>test description</p>\n".to_owned(),
+                created_at: chrono::offset::Utc::now(),
+            },
+            answers: vec![],
+            answer_count: 0,
+            view_count: 3,
+        }));
+
+        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+        let views = ViewCounter::new(Duration::from_secs(60));
+        let viewer = Some(Ipv4Addr::LOCALHOST.into());
+
+        let result = read_question(question_uuid, viewer, questions_dao.as_ref(), &views).await;
+
+        assert_eq!(result.unwrap().view_count, 4);
+    }
+
+    #[tokio::test]
+    async fn read_question_should_return_not_found_error() {
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_get_question(Err(DBError::NotFound("test".to_owned())));
+
+        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+
+        let views = ViewCounter::new(Duration::from_secs(60));
+        let viewer = Some(Ipv4Addr::LOCALHOST.into());
+
+        let result = read_question(
+            QuestionId(Uuid::nil()),
+            viewer,
+            questions_dao.as_ref(),
+            &views,
+        )
+        .await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+        assert_eq!(views.pending(QuestionId(Uuid::nil())), 0);
+    }
+
     #[tokio::test]
     async fn read_questions_should_return_questions() {
         let question_detail = Question {
-            question_uuid: Uuid::new_v4(),
+            question_uuid: QuestionId(Uuid::new_v4()),
             detail: QuestionFields {
                 title: "test title".to_owned(),
                 description: "test description".to_owned(),
             },
+            description_html: "<p>test description</p>\n".to_owned(),
             created_at: chrono::offset::Utc::now(),
         };
 
@@ -268,7 +1000,7 @@ mod tests {
     async fn read_questions_should_return_error() {
         let mut questions_dao = QuestionsDaoMock::new();
 
-        questions_dao.mock_get_questions(Err(DBError::InvalidUUID("test".to_owned())));
+        questions_dao.mock_get_questions(Err(DBError::Other(Box::new(Error::PoolTimedOut))));
 
         let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
 
@@ -283,15 +1015,15 @@ mod tests {
 
     #[tokio::test]
     async fn delete_question_should_succeed() {
-        let question_id = Uuid::new_v4();
+        let question_id = QuestionId(Uuid::new_v4());
 
         let mut questions_dao = QuestionsDaoMock::new();
 
         questions_dao.mock_delete_question(Ok(()));
 
-        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
 
-        let result = delete_question(question_id, questions_dao.as_ref()).await;
+        let result = delete_question(question_id, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), ());
@@ -299,15 +1031,15 @@ mod tests {
 
     #[tokio::test]
     async fn delete_question_should_return_error() {
-        let question_id = Uuid::nil();
+        let question_id = QuestionId(Uuid::nil());
 
         let mut questions_dao = QuestionsDaoMock::new();
 
         questions_dao.mock_delete_question(Err(DBError::Other(Box::new(Error::PoolTimedOut))));
 
-        let questions_dao: Box<dyn QuestionDAO + Send + Sync> = Box::new(questions_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
 
-        let result = delete_question(question_id, questions_dao.as_ref()).await;
+        let result = delete_question(question_id, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_err());
         assert!(
@@ -317,18 +1049,122 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn create_answer_should_return_answer() {
-        let answer = AnswerFields {
-            question_uuid: Uuid::new_v4(),
-            content: "test content".to_owned(),
+    async fn delete_question_should_return_not_found_error() {
+        let question_id = QuestionId(Uuid::nil());
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_delete_question(Err(DBError::NotFound("test".to_owned())));
+
+        let (dao, _) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
+
+        let result = delete_question(question_id, None, &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+    }
+
+    fn question_detail(question_uuid: QuestionId) -> QuestionDetail {
+        return QuestionDetail {
+            question: Question {
+                question_uuid,
+                detail: QuestionFields {
+                    title: "test title".to_owned(),
+                    description: "test description".to_owned(),
+                },
+                description_html: "<p>test description</p>\n".to_owned(),
+                created_at: chrono::offset::Utc::now(),
+            },
+            answers: vec![],
+            answer_count: 0,
+            view_count: 0,
+        };
+    }
+
+    #[tokio::test]
+    async fn delete_question_with_condition_should_delete_current_question() {
+        let question_id = QuestionId(Uuid::new_v4());
+        let current = question_detail(question_id);
+        let condition = etag(to_json(&current.content()).as_bytes());
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_get_question(Ok(current));
+        questions_dao.mock_delete_question(Ok(()));
+
+        let (dao, committed) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
+
+        let result =
+            delete_question(question_id, Some(&condition), &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Ok(()));
+        assert!(committed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn delete_question_with_condition_should_ignore_views_since_read() {
+        let question_id = QuestionId(Uuid::new_v4());
+        let views = ViewCounter::new(Duration::from_secs(60));
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_get_question(Ok(question_detail(question_id)));
+
+        let read = read_question(question_id, None, &questions_dao, &views)
+            .await
+            .unwrap();
+        let condition = etag(to_json(&read.content()).as_bytes());
+
+        // Another reader views the question before it is deleted
+        views.record(question_id, IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        let mut current = question_detail(question_id);
+
+        current.question = read.question;
+        current.view_count = 1;
+        questions_dao.mock_get_question(Ok(current));
+        questions_dao.mock_delete_question(Ok(()));
+
+        let (dao, committed) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
+
+        let result =
+            delete_question(question_id, Some(&condition), &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Ok(()));
+        assert!(committed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn delete_question_with_condition_should_not_delete_changed_question() {
+        let question_id = QuestionId(Uuid::new_v4());
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_get_question(Ok(question_detail(question_id)));
+
+        let (dao, committed) = UnitOfWorkDaoMock::new(questions_dao, AnswersDaoMock::new());
+
+        let result =
+            delete_question(question_id, Some("\"stale\""), &dao, &ActivityFeed::new()).await;
+
+        assert!(matches!(result, Err(HandlerError::PreconditionFailed(_))));
+        assert!(!committed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::clone_on_copy)]
+    async fn create_answer_should_return_answer() {
+        let answer = AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "test content".to_owned(),
         };
 
         let answer_detail = Answer {
-            answer_uuid: Uuid::new_v4(),
+            answer_uuid: AnswerId(Uuid::new_v4()),
             detail: AnswerFields {
                 question_uuid: answer.question_uuid.clone(),
                 content: answer.content.clone(),
             },
+            content_html: "<p>test content</p>\n".to_owned(),
             created_at: chrono::offset::Utc::now(),
         };
 
@@ -336,40 +1172,65 @@ mod tests {
 
         answers_dao.mock_create_answer(Ok(answer_detail.clone()));
 
-        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
 
-        let result = create_answer(answer, answers_dao.as_ref()).await;
+        let result = create_answer(answer, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), answer_detail);
+        assert_eq!(result.unwrap(), (answer_detail, false));
     }
 
     #[tokio::test]
-    async fn create_answer_should_return_bad_request_error() {
+    async fn create_answer_should_publish_answer() {
         let answer = AnswerFields {
-            question_uuid: Uuid::new_v4(),
+            question_uuid: QuestionId(Uuid::new_v4()),
             content: "test content".to_owned(),
         };
+        let answer_detail = Answer::new(answer.clone());
 
         let mut answers_dao = AnswersDaoMock::new();
 
-        answers_dao.mock_create_answer(Err(DBError::InvalidUUID("test".to_owned())));
+        answers_dao.mock_create_answer(Ok(answer_detail.clone()));
 
-        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
+        let activity = ActivityFeed::new();
+        let mut answers = Box::pin(activity.new_answers(answer.question_uuid));
 
-        let result = create_answer(answer, answers_dao.as_ref()).await;
+        create_answer(answer, None, &dao, &activity).await.unwrap();
+
+        assert_eq!(answers.next().await, Some(answer_detail));
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_return_unprocessable_entity_error() {
+        let answer = AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "test content".to_owned(),
+        };
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_create_answer(Err(DBError::CheckViolation("test".to_owned())));
+
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
+
+        let result = create_answer(answer, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_err());
         assert!(
             std::mem::discriminant(&result.unwrap_err())
-                == std::mem::discriminant(&HandlerError::BadRequest("".to_owned()))
+                == std::mem::discriminant(&HandlerError::UnprocessableEntity(
+                    "".to_owned(),
+                    vec![]
+                ))
         );
     }
 
     #[tokio::test]
+    #[allow(clippy::io_other_error)]
     async fn create_answer_should_return_internal_error() {
         let answer = AnswerFields {
-            question_uuid: Uuid::new_v4(),
+            question_uuid: QuestionId(Uuid::new_v4()),
             content: "test content".to_owned(),
         };
 
@@ -380,9 +1241,9 @@ mod tests {
             "oh no!",
         )))));
 
-        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
 
-        let result = create_answer(answer, answers_dao.as_ref()).await;
+        let result = create_answer(answer, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_err());
         assert!(
@@ -391,18 +1252,114 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn create_answer_should_return_not_found_error() {
+        let answer = AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "test content".to_owned(),
+        };
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_create_answer(Err(DBError::ForeignKeyViolation("test".to_owned())));
+
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
+
+        let result = create_answer(answer, None, &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+    }
+
+    fn self_answered_question() -> SelfAnsweredQuestionFields {
+        return SelfAnsweredQuestionFields {
+            question: QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            },
+            answer: "test answer".to_owned(),
+        };
+    }
+
+    #[tokio::test]
+    async fn create_self_answered_question_should_commit_question_and_answer() {
+        let question = Question {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            detail: QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            },
+            description_html: "<p>test description</p>\n".to_owned(),
+            created_at: chrono::offset::Utc::now(),
+        };
+        let answer = Answer::new(AnswerFields {
+            question_uuid: question.question_uuid,
+            content: "test answer".to_owned(),
+        });
+
+        let mut questions_dao = QuestionsDaoMock::new();
+        let mut answers_dao = AnswersDaoMock::new();
+
+        questions_dao.mock_create_question(Ok(question.clone()));
+        answers_dao.mock_create_answer(Ok(answer.clone()));
+
+        let (dao, committed) = UnitOfWorkDaoMock::new(questions_dao, answers_dao);
+        let activity = ActivityFeed::new();
+        let mut answers = Box::pin(activity.new_answers(question.question_uuid));
+
+        let result = create_self_answered_question(self_answered_question(), &dao, &activity).await;
+
+        assert_eq!(
+            result,
+            Ok(QuestionDetail {
+                question,
+                answers: vec![answer.clone()],
+                answer_count: 1,
+                view_count: 0,
+            })
+        );
+        assert!(committed.load(Ordering::SeqCst));
+        assert_eq!(answers.next().await, Some(answer));
+    }
+
+    #[tokio::test]
+    async fn create_self_answered_question_should_not_commit_if_answer_fails() {
+        let mut questions_dao = QuestionsDaoMock::new();
+        let mut answers_dao = AnswersDaoMock::new();
+
+        questions_dao.mock_create_question(Ok(Question {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            detail: QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            },
+            description_html: "<p>test description</p>\n".to_owned(),
+            created_at: chrono::offset::Utc::now(),
+        }));
+        answers_dao.mock_create_answer(Err(DBError::Other(Box::new(Error::PoolTimedOut))));
+
+        let (dao, committed) = UnitOfWorkDaoMock::new(questions_dao, answers_dao);
+
+        let result =
+            create_self_answered_question(self_answered_question(), &dao, &ActivityFeed::new())
+                .await;
+
+        assert!(matches!(result, Err(HandlerError::InternalError(_))));
+        assert!(!committed.load(Ordering::SeqCst));
+    }
+
     #[tokio::test]
     async fn read_answers_should_return_answers() {
         let answer_detail = Answer {
-            answer_uuid: Uuid::new_v4(),
+            answer_uuid: AnswerId(Uuid::new_v4()),
             detail: AnswerFields {
-                question_uuid: Uuid::new_v4(),
+                question_uuid: QuestionId(Uuid::new_v4()),
                 content: "test content".to_owned(),
             },
+            content_html: "<p>test content</p>\n".to_owned(),
             created_at: chrono::offset::Utc::now(),
         };
 
-        let question_id = Uuid::new_v4();
+        let question_id = QuestionId(Uuid::new_v4());
 
         let mut answers_dao = AnswersDaoMock::new();
 
@@ -418,7 +1375,7 @@ mod tests {
 
     #[tokio::test]
     async fn read_answers_should_return_error() {
-        let question_id = Uuid::new_v4();
+        let question_id = QuestionId(Uuid::new_v4());
 
         let mut answers_dao = AnswersDaoMock::new();
 
@@ -435,33 +1392,106 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn read_answer_should_return_answer() {
+        let answer_detail = Answer::new(AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "test content".to_owned(),
+        });
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_get_answer(Ok(answer_detail.clone()));
+
+        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+
+        let result = read_answer(answer_detail.answer_uuid, answers_dao.as_ref()).await;
+
+        assert_eq!(result, Ok(answer_detail));
+    }
+
+    #[tokio::test]
+    async fn read_answer_should_return_not_found_error() {
+        let answer_id = AnswerId(Uuid::new_v4());
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_get_answer(Err(DBError::NotFound("test".to_owned())));
+
+        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+
+        let result = read_answer(answer_id, answers_dao.as_ref()).await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+    }
+
     #[tokio::test]
     async fn delete_answer_should_succeed() {
-        let answer_id = Uuid::new_v4();
+        let answer_id = AnswerId(Uuid::new_v4());
 
         let mut answers_dao = AnswersDaoMock::new();
 
         answers_dao.mock_delete_answer(Ok(()));
 
-        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
 
-        let result = delete_answer(answer_id, answers_dao.as_ref()).await;
+        let result = delete_answer(answer_id, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), ());
     }
 
+    #[tokio::test]
+    async fn delete_answer_should_return_not_found_error() {
+        let answer_id = AnswerId(Uuid::new_v4());
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_delete_answer(Err(DBError::NotFound("test".to_owned())));
+
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
+
+        let result = delete_answer(answer_id, None, &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn delete_answer_with_condition_should_not_delete_changed_answer() {
+        let answer = Answer::new(AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "test content".to_owned(),
+        });
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_get_answer(Ok(answer.clone()));
+
+        let (dao, committed) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
+
+        let result = delete_answer(
+            answer.answer_uuid,
+            Some("\"stale\""),
+            &dao,
+            &ActivityFeed::new(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(HandlerError::PreconditionFailed(_))));
+        assert!(!committed.load(Ordering::SeqCst));
+    }
+
     #[tokio::test]
     async fn delete_answer_should_return_error() {
-        let answer_id = Uuid::new_v4();
+        let answer_id = AnswerId(Uuid::new_v4());
 
         let mut answers_dao = AnswersDaoMock::new();
 
         answers_dao.mock_delete_answer(Err(DBError::Other(Box::new(Error::PoolClosed))));
 
-        let answers_dao: Box<dyn AnswerDAO + Send + Sync> = Box::new(answers_dao);
+        let (dao, _) = UnitOfWorkDaoMock::new(QuestionsDaoMock::new(), answers_dao);
 
-        let result = delete_answer(answer_id, answers_dao.as_ref()).await;
+        let result = delete_answer(answer_id, None, &dao, &ActivityFeed::new()).await;
 
         assert!(result.is_err());
         assert!(
@@ -469,4 +1499,281 @@ mod tests {
                 == std::mem::discriminant(&HandlerError::InternalError("".to_owned()))
         );
     }
+
+    #[test]
+    fn fingerprint_should_tell_endpoints_and_bodies_apart() {
+        let question = QuestionFields {
+            title: "test title".to_owned(),
+            description: "test description".to_owned(),
+        };
+        let mut other = question.clone();
+
+        other.description.push('!');
+
+        let original = fingerprint("POST /question", &question);
+
+        assert_eq!(original, fingerprint("POST /question", &question));
+        assert_ne!(original, fingerprint("POST /answer", &question));
+        assert_ne!(original, fingerprint("POST /question", &other));
+    }
+
+    fn created_question(question: &QuestionFields) -> Question {
+        return Question {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            detail: question.clone(),
+            description_html: "<p>test description</p>\n".to_owned(),
+            created_at: chrono::offset::Utc::now(),
+        };
+    }
+
+    #[tokio::test]
+    async fn create_question_should_store_response_of_claimed_key() {
+        let question = QuestionFields {
+            title: "test title".to_owned(),
+            description: "test description".to_owned(),
+        };
+        let created = created_question(&question);
+
+        let mut questions_dao = QuestionsDaoMock::new();
+
+        questions_dao.mock_create_question(Ok(created.clone()));
+
+        let mut work = UnitOfWorkMock::new(questions_dao, AnswersDaoMock::new());
+
+        work.idempotency
+            .mock_claim(Ok(Claim::Claimed(Uuid::new_v4())));
+
+        let completed = work.idempotency.completed.clone();
+        let (dao, committed) = UnitOfWorkDaoMock::of(work);
+
+        let result = create_question(question, Some("key"), &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Ok((created.clone(), false)));
+        assert_eq!(*completed.lock().await, Some(to_json(&created)));
+        assert!(committed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn create_question_should_replay_completed_response() {
+        let question = QuestionFields {
+            title: "test title".to_owned(),
+            description: "test description".to_owned(),
+        };
+        let created = created_question(&question);
+
+        // Creating the question again would panic
+        let mut work = UnitOfWorkMock::new(QuestionsDaoMock::new(), AnswersDaoMock::new());
+
+        work.idempotency.mock_claim(Ok(Claim::Completed(
+            serde_json::to_value(&created).unwrap(),
+        )));
+
+        let (dao, committed) = UnitOfWorkDaoMock::of(work);
+
+        let result = create_question(question, Some("key"), &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Ok((created, true)));
+        assert!(!committed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn create_question_should_reject_reused_key() {
+        let question = QuestionFields {
+            title: "test title".to_owned(),
+            description: "test description".to_owned(),
+        };
+
+        let mut work = UnitOfWorkMock::new(QuestionsDaoMock::new(), AnswersDaoMock::new());
+
+        work.idempotency.mock_claim(Ok(Claim::Mismatch));
+
+        let (dao, _) = UnitOfWorkDaoMock::of(work);
+
+        let result = create_question(question, Some("key"), &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(
+            result,
+            Err(HandlerError::UnprocessableEntity(
+                "The idempotency key was already used for a different request.".to_owned(),
+                Vec::new()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn create_answer_should_not_commit_key_of_failed_request() {
+        let answer = AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "test content".to_owned(),
+        };
+
+        let mut answers_dao = AnswersDaoMock::new();
+
+        answers_dao.mock_create_answer(Err(DBError::ForeignKeyViolation("test".to_owned())));
+
+        let mut work = UnitOfWorkMock::new(QuestionsDaoMock::new(), answers_dao);
+
+        work.idempotency
+            .mock_claim(Ok(Claim::Claimed(Uuid::new_v4())));
+
+        let completed = work.idempotency.completed.clone();
+        let (dao, committed) = UnitOfWorkDaoMock::of(work);
+
+        let result = create_answer(answer, Some("key"), &dao, &ActivityFeed::new()).await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+        assert_eq!(*completed.lock().await, None);
+        assert!(!committed.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn create_webhook_should_return_webhook() {
+        let webhook = WebhookFields {
+            url: "https://example.com/hook".to_owned(),
+            events: vec![WebhookEvent::AnswerCreated],
+            secret: "a secret that is long enough".to_owned(),
+        };
+
+        let webhook_detail = Webhook {
+            webhook_uuid: WebhookId(Uuid::new_v4()),
+            url: webhook.url.clone(),
+            events: webhook.events.clone(),
+            created_at: chrono::offset::Utc::now(),
+        };
+
+        let mut webhooks_dao = WebhooksDaoMock::new();
+
+        webhooks_dao.mock_create_webhook(Ok(webhook_detail.clone()));
+
+        let webhooks_dao: Box<dyn WebhookDAO + Send + Sync> = Box::new(webhooks_dao);
+
+        let result = create_webhook(webhook, webhooks_dao.as_ref()).await;
+
+        assert_eq!(result, Ok(webhook_detail));
+    }
+
+    #[tokio::test]
+    async fn delete_webhook_should_return_not_found_error() {
+        let mut webhooks_dao = WebhooksDaoMock::new();
+
+        webhooks_dao.mock_delete_webhook(Err(DBError::NotFound("test".to_owned())));
+
+        let webhooks_dao: Box<dyn WebhookDAO + Send + Sync> = Box::new(webhooks_dao);
+
+        let result = delete_webhook(WebhookId(Uuid::nil()), webhooks_dao.as_ref()).await;
+
+        assert_eq!(result, Err(HandlerError::NotFound("test".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn read_deliveries_should_return_error() {
+        let mut webhooks_dao = WebhooksDaoMock::new();
+
+        webhooks_dao.mock_get_deliveries(Err(DBError::Other(Box::new(Error::PoolTimedOut))));
+
+        let webhooks_dao: Box<dyn WebhookDAO + Send + Sync> = Box::new(webhooks_dao);
+
+        let result = read_deliveries(WebhookId(Uuid::new_v4()), webhooks_dao.as_ref()).await;
+
+        assert!(
+            std::mem::discriminant(&result.unwrap_err())
+                == std::mem::discriminant(&HandlerError::InternalError("".to_owned()))
+        );
+    }
+
+    #[tokio::test]
+    async fn import_records_should_parse_every_line() {
+        let question = Question {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            detail: QuestionFields {
+                title: "How do I import records?".to_owned(),
+                description: "A description that is long enough to be imported.".to_owned(),
+            },
+            description_html: "<p>A description that is long enough to be imported.</p>\n"
+                .to_owned(),
+            created_at: chrono::offset::Utc::now(),
+        };
+        let answer = Answer::new(AnswerFields {
+            question_uuid: question.question_uuid,
+            content: "An answer that is long enough to be imported.".to_owned(),
+        });
+        let records = vec![
+            ExportRecord::Question(question),
+            ExportRecord::Answer(answer),
+        ];
+        let lines = records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap() + "\n\n")
+            .collect::<String>();
+        let summary = ImportSummary {
+            questions: 1,
+            answers: 1,
+            dry_run: true,
+        };
+
+        let mut backup_dao = BackupDaoMock::new();
+
+        backup_dao.mock_import(Ok(summary.clone()));
+
+        let result = import_records(&lines, true, &backup_dao).await;
+
+        assert_eq!(result, Ok(summary));
+        assert_eq!(*backup_dao.imported.lock().await, records);
+    }
+
+    #[tokio::test]
+    async fn import_records_should_reject_invalid_line() {
+        let backup_dao = BackupDaoMock::new();
+
+        let result = import_records("\n{\"kind\": \"vote\"}\n", false, &backup_dao).await;
+
+        match result {
+            Err(HandlerError::UnprocessableEntity(message, _)) => {
+                assert!(message.starts_with("Line 2 is not a valid record"))
+            }
+            result => panic!(
+                "Expected an unprocessable entity error but got {:?}",
+                result
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn import_records_should_reject_invalid_record() {
+        let answer = Answer::new(AnswerFields {
+            question_uuid: QuestionId(Uuid::new_v4()),
+            content: "too short".to_owned(),
+        });
+        let lines = format!("\n{}\n", to_json(&ExportRecord::Answer(answer)));
+
+        // Importing would panic
+        let backup_dao = BackupDaoMock::new();
+
+        let result = import_records(&lines, false, &backup_dao).await;
+
+        assert_eq!(
+            result,
+            Err(HandlerError::UnprocessableEntity(
+                "Line 2 failed validation.".to_owned(),
+                vec![FieldError {
+                    field: "content".to_owned(),
+                    message: "must be between 30 and 30000 characters".to_owned(),
+                }]
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn import_records_should_reject_dangling_answers() {
+        let mut backup_dao = BackupDaoMock::new();
+
+        backup_dao.mock_import(Err(DBError::ForeignKeyViolation("test".to_owned())));
+
+        let result = import_records("", false, &backup_dao).await;
+
+        assert_eq!(
+            result,
+            Err(HandlerError::UnprocessableEntity("test".to_owned(), vec![]))
+        );
+    }
 }
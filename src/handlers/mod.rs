@@ -1,85 +1,849 @@
+mod backup;
+pub mod conditional;
+mod extractors;
+mod feeds;
 pub mod inner;
+mod socket;
+mod streams;
+
+use crate::{markdown, models::*, telemetry, AppState};
+use std::net::SocketAddr;
 
-use crate::{models::*, AppState};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 
+pub use backup::*;
+use conditional::{conditional_json, conditional_json_of, if_match};
+use extractors::{Admin, IdempotencyKey, ValidatedJson};
+pub use feeds::*;
 use inner::*;
-use uuid::Uuid;
+use serde::Serialize;
+pub use socket::socket;
+pub use streams::*;
+use tracing::instrument;
 
-impl IntoResponse for HandlerError {
-    fn into_response(self) -> Response {
+/// Request header naming a POST, so a retry of it is answered with the first response.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Response header set to `true` when the response is a replay of an earlier request's.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+impl HandlerError {
+    fn status(&self) -> StatusCode {
         return match self {
-            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message).into_response(),
-            Self::InternalError(message) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
-            }
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnprocessableEntity(..) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+    }
+
+    /// Stable, machine readable identifier of the error kind. Never rename these.
+    fn code(&self) -> &'static str {
+        return match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::PreconditionFailed(_) => "precondition_failed",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::UnprocessableEntity(..) => "unprocessable_entity",
+            Self::InternalError(_) => "internal_error",
+        };
+    }
+}
+
+impl From<HandlerError> for ProblemDetails {
+    fn from(error: HandlerError) -> Self {
+        let status = error.status();
+        let code = error.code();
+        let (detail, errors) = match error {
+            HandlerError::UnprocessableEntity(message, errors) => (message, errors),
+            HandlerError::BadRequest(message)
+            | HandlerError::Unauthorized(message)
+            | HandlerError::Forbidden(message)
+            | HandlerError::NotFound(message)
+            | HandlerError::Conflict(message)
+            | HandlerError::PreconditionFailed(message)
+            | HandlerError::PayloadTooLarge(message)
+            | HandlerError::InternalError(message) => (message, Vec::new()),
+        };
+
+        return Self {
+            problem_type: format!("/errors/{}", code),
+            title: status.canonical_reason().unwrap_or_default().to_owned(),
+            status: status.as_u16(),
+            detail,
+            code: code.to_owned(),
+            request_id: telemetry::current_request_id(),
+            errors,
         };
     }
 }
 
+/// Responds with `value` as JSON, marking replays of an earlier response.
+fn idempotent_json<T: Serialize>((value, replayed): (T, bool)) -> Response {
+    if replayed {
+        return ([(IDEMPOTENT_REPLAYED_HEADER, "true")], Json(value)).into_response();
+    }
+
+    return Json(value).into_response();
+}
+
+impl IntoResponse for HandlerError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+
+        return (
+            status,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(ProblemDetails::from(self)),
+        )
+            .into_response();
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/question",
+    tag = "questions",
+    params(
+        (
+            "Idempotency-Key" = Option<String>,
+            Header,
+            description = "Key unique to the request for 24 hours. Retries with the same key get \
+                the first response instead of creating another question"
+        ),
+    ),
+    request_body = QuestionFields,
+    responses(
+        (
+            status = 200,
+            description = "The created question",
+            body = Question,
+            headers((
+                "Idempotent-Replayed" = bool,
+                description = "Set when the question was created by an earlier request with the same key"
+            ))
+        ),
+        (
+            status = 400,
+            description = "The request body or idempotency key is malformed",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 413,
+            description = "The request body is too large",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 422,
+            description = "The request body failed validation, or the idempotency key was used \
+                for a different request",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn create_question(
-    State(AppState { questions_dao, .. }): State<AppState>,
-    Json(question): Json<QuestionFields>,
+    State(AppState {
+        unit_of_work_dao,
+        activity,
+        ..
+    }): State<AppState>,
+    IdempotencyKey(key): IdempotencyKey,
+    ValidatedJson(question): ValidatedJson<QuestionFields>,
+) -> Result<Response, HandlerError> {
+    let result = inner::create_question(
+        question,
+        key.as_deref(),
+        unit_of_work_dao.as_ref(),
+        &activity,
+    )
+    .await?;
+
+    return Ok(idempotent_json(result));
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/self-answered",
+    tag = "questions",
+    request_body = SelfAnsweredQuestionFields,
+    responses(
+        (status = 200, description = "The created question and its answer", body = QuestionDetail),
+        (
+            status = 400,
+            description = "The request body is malformed",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 413,
+            description = "The request body is too large",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 422,
+            description = "The request body failed validation",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error; neither the question nor the answer was created",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn create_self_answered_question(
+    State(AppState {
+        unit_of_work_dao,
+        activity,
+        ..
+    }): State<AppState>,
+    ValidatedJson(fields): ValidatedJson<SelfAnsweredQuestionFields>,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
-    return inner::create_question(question, questions_dao.as_ref())
+    return inner::create_self_answered_question(fields, unit_of_work_dao.as_ref(), &activity)
         .await
         .map(Json);
 }
 
+#[utoipa::path(
+    get,
+    path = "/questions",
+    tag = "questions",
+    responses(
+        (status = 200, description = "Every question", body = Vec<Question>),
+        (status = 304, description = "Not modified since the `ETag` sent before"),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn read_questions(
     State(AppState { questions_dao, .. }): State<AppState>,
-) -> impl IntoResponse {
-    return inner::read_questions(questions_dao.as_ref())
-        .await
-        .map(Json);
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let questions = inner::read_questions(questions_dao.as_ref()).await?;
+
+    // Deletions do not move the newest creation date back, so only the ETag validates lists
+    return Ok(conditional_json(&questions, None, &headers));
 }
 
-pub async fn delete_question(
-    State(AppState { questions_dao, .. }): State<AppState>,
-    Path(id): Path<String>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
-    return inner::delete_question(
-        Uuid::parse_str(&id).unwrap_or(Uuid::nil()),
-        questions_dao.as_ref(),
+#[utoipa::path(
+    get,
+    path = "/question/{id}",
+    tag = "questions",
+    params(("id" = Uuid, Path, description = "ID of the question to read")),
+    responses(
+        (status = 200, description = "The question and its answers", body = QuestionDetail),
+        (
+            status = 304,
+            description = "Neither the question nor its answers changed since the `ETag` sent \
+                before, though the view count may have"
+        ),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
     )
-    .await
-    .map(Json);
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn read_question(
+    State(AppState {
+        questions_dao,
+        view_counter,
+        ..
+    }): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    id: QuestionId,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let viewer = connect_info.map(|ConnectInfo(address)| address.ip());
+
+    let question = inner::read_question(id, viewer, questions_dao.as_ref(), &view_counter).await?;
+
+    // Views and deleted answers leave every date as it was, so only the ETag validates it. Views
+    // alone do not change the ETag, so that `If-Match` holds across other readers.
+    return Ok(conditional_json_of(
+        &question,
+        &question.content(),
+        None,
+        &headers,
+    ));
 }
 
+#[utoipa::path(
+    delete,
+    path = "/question/{id}",
+    tag = "questions",
+    params(
+        ("id" = Uuid, Path, description = "ID of the question to delete"),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "Only delete the question if this is still its `ETag`"
+        ),
+    ),
+    responses(
+        (status = 200, description = "The question was deleted"),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 412,
+            description = "The `If-Match` ETag is not the current one",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn delete_question(
+    State(AppState {
+        unit_of_work_dao,
+        activity,
+        ..
+    }): State<AppState>,
+    id: QuestionId,
+    headers: HeaderMap,
+) -> Result<Json<()>, HandlerError> {
+    inner::delete_question(id, if_match(&headers), unit_of_work_dao.as_ref(), &activity).await?;
+
+    return Ok(Json(()));
+}
+
+#[utoipa::path(
+    post,
+    path = "/answer",
+    tag = "answers",
+    params(
+        (
+            "Idempotency-Key" = Option<String>,
+            Header,
+            description = "Key unique to the request for 24 hours. Retries with the same key get \
+                the first response instead of creating another answer"
+        ),
+    ),
+    request_body = AnswerFields,
+    responses(
+        (
+            status = 200,
+            description = "The created answer",
+            body = Answer,
+            headers((
+                "Idempotent-Replayed" = bool,
+                description = "Set when the answer was created by an earlier request with the same key"
+            ))
+        ),
+        (
+            status = 400,
+            description = "The request body or idempotency key is malformed",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 413,
+            description = "The request body is too large",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 422,
+            description = "The request body failed validation, or the idempotency key was used \
+                for a different request",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn create_answer(
+    State(AppState {
+        unit_of_work_dao,
+        activity,
+        ..
+    }): State<AppState>,
+    IdempotencyKey(key): IdempotencyKey,
+    ValidatedJson(answer): ValidatedJson<AnswerFields>,
+) -> Result<Response, HandlerError> {
+    let result =
+        inner::create_answer(answer, key.as_deref(), unit_of_work_dao.as_ref(), &activity).await?;
+
+    return Ok(idempotent_json(result));
+}
+
+#[utoipa::path(
+    get,
+    path = "/answers/{question_id}",
+    tag = "answers",
+    params(("question_id" = Uuid, Path, description = "ID of the answered question")),
+    responses(
+        (status = 200, description = "Every answer to the question", body = Vec<Answer>),
+        (status = 304, description = "Not modified since the `ETag` sent before"),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %question_id))]
+pub async fn read_answers(
     State(AppState { answers_dao, .. }): State<AppState>,
-    Json(answer): Json<AnswerFields>,
+    question_id: QuestionId,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let answers = inner::read_answers(question_id, answers_dao.as_ref()).await?;
+
+    // Deletions do not move the newest creation date back, so only the ETag validates lists
+    return Ok(conditional_json(&answers, None, &headers));
+}
+
+#[utoipa::path(
+    get,
+    path = "/answer/{id}",
+    tag = "answers",
+    params(("id" = Uuid, Path, description = "ID of the answer to read")),
+    responses(
+        (status = 200, description = "The answer", body = Answer),
+        (status = 304, description = "Not modified since the `ETag` or `Last-Modified` sent before"),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The answer does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn read_answer(
+    State(AppState { answers_dao, .. }): State<AppState>,
+    id: AnswerId,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError> {
+    let answer = inner::read_answer(id, answers_dao.as_ref()).await?;
+
+    return Ok(conditional_json(&answer, Some(answer.created_at), &headers));
+}
+
+#[utoipa::path(
+    delete,
+    path = "/answer/{id}",
+    tag = "answers",
+    params(
+        ("id" = Uuid, Path, description = "ID of the answer to delete"),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "Only delete the answer if this is still its `ETag`"
+        ),
+    ),
+    responses(
+        (status = 200, description = "The answer was deleted"),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The answer does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 412,
+            description = "The `If-Match` ETag is not the current one",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn delete_answer(
+    State(AppState {
+        unit_of_work_dao,
+        activity,
+        ..
+    }): State<AppState>,
+    id: AnswerId,
+    headers: HeaderMap,
+) -> Result<Json<()>, HandlerError> {
+    inner::delete_answer(id, if_match(&headers), unit_of_work_dao.as_ref(), &activity).await?;
+
+    return Ok(Json(()));
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhook",
+    tag = "webhooks",
+    security(("admin_token" = [])),
+    request_body = WebhookFields,
+    responses(
+        (status = 200, description = "The created webhook subscription", body = Webhook),
+        (
+            status = 400,
+            description = "The request body is malformed",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 401,
+            description = "No admin token was given",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "The admin token is not valid or admin endpoints are disabled",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 413,
+            description = "The request body is too large",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 422,
+            description = "The request body failed validation",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn create_webhook(
+    _: Admin,
+    State(AppState { webhooks_dao, .. }): State<AppState>,
+    ValidatedJson(webhook): ValidatedJson<WebhookFields>,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
-    return inner::create_answer(answer, answers_dao.as_ref())
+    return inner::create_webhook(webhook, webhooks_dao.as_ref())
         .await
         .map(Json);
 }
 
-pub async fn read_answers(
-    State(AppState { answers_dao, .. }): State<AppState>,
-    Path(question_id): Path<String>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
-    return inner::read_answers(
-        Uuid::parse_str(&question_id).unwrap_or(Uuid::nil()),
-        answers_dao.as_ref(),
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    security(("admin_token" = [])),
+    responses(
+        (status = 200, description = "Every webhook subscription", body = Vec<Webhook>),
+        (
+            status = 401,
+            description = "No admin token was given",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "The admin token is not valid or admin endpoints are disabled",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
     )
-    .await
-    .map(Json);
+)]
+#[instrument(skip_all)]
+pub async fn read_webhooks(
+    _: Admin,
+    State(AppState { webhooks_dao, .. }): State<AppState>,
+) -> impl IntoResponse {
+    return inner::read_webhooks(webhooks_dao.as_ref()).await.map(Json);
 }
 
-pub async fn delete_answer(
-    State(AppState { answers_dao, .. }): State<AppState>,
-    Path(id): Path<String>,
+#[utoipa::path(
+    delete,
+    path = "/webhook/{id}",
+    tag = "webhooks",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID of the webhook to delete")),
+    responses(
+        (status = 200, description = "The webhook and its delivery log were deleted"),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 401,
+            description = "No admin token was given",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "The admin token is not valid or admin endpoints are disabled",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The webhook does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn delete_webhook(
+    _: Admin,
+    State(AppState { webhooks_dao, .. }): State<AppState>,
+    id: WebhookId,
 ) -> Result<impl IntoResponse, impl IntoResponse> {
-    return inner::delete_answer(
-        Uuid::parse_str(&id).unwrap_or(Uuid::nil()),
-        answers_dao.as_ref(),
+    return inner::delete_webhook(id, webhooks_dao.as_ref())
+        .await
+        .map(Json);
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhook/{id}/deliveries",
+    tag = "webhooks",
+    security(("admin_token" = [])),
+    params(("id" = Uuid, Path, description = "ID of the webhook")),
+    responses(
+        (
+            status = 200,
+            description = "Every delivery to the webhook, newest first",
+            body = Vec<WebhookDelivery>
+        ),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 401,
+            description = "No admin token was given",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 403,
+            description = "The admin token is not valid or admin endpoints are disabled",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The webhook does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
     )
-    .await
-    .map(Json);
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn read_deliveries(
+    _: Admin,
+    State(AppState { webhooks_dao, .. }): State<AppState>,
+    id: WebhookId,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    return inner::read_deliveries(id, webhooks_dao.as_ref())
+        .await
+        .map(Json);
+}
+
+pub async fn fallback() -> HandlerError {
+    return HandlerError::NotFound(String::from("No route matches the requested path."));
+}
+
+#[instrument(skip_all)]
+pub async fn read_metrics(
+    State(AppState {
+        database,
+        metrics_handle,
+        ..
+    }): State<AppState>,
+) -> impl IntoResponse {
+    telemetry::record_pool_metrics(&database);
+
+    return metrics_handle.render();
+}
+
+/// Stylesheet for the classes emitted when highlighting code blocks in rendered markdown.
+pub async fn read_highlight_css() -> impl IntoResponse {
+    return (
+        [(header::CONTENT_TYPE, "text/css")],
+        markdown::highlight_css(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn handler_error_should_render_problem_details() {
+        let response = HandlerError::NotFound("Question not found".to_owned()).into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let problem: ProblemDetails = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            problem,
+            ProblemDetails {
+                problem_type: "/errors/not_found".to_owned(),
+                title: "Not Found".to_owned(),
+                status: 404,
+                detail: "Question not found".to_owned(),
+                code: "not_found".to_owned(),
+                request_id: None,
+                errors: vec![],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn unprocessable_entity_should_list_field_errors() {
+        let field_error = FieldError {
+            field: "title".to_owned(),
+            message: "must not be empty".to_owned(),
+        };
+
+        let problem = ProblemDetails::from(HandlerError::UnprocessableEntity(
+            "Invalid question".to_owned(),
+            vec![field_error.clone()],
+        ));
+
+        assert_eq!(problem.status, 422);
+        assert_eq!(problem.code, "unprocessable_entity");
+        assert_eq!(problem.errors, vec![field_error]);
+    }
 }
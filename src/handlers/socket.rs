@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt;
+use tracing::instrument;
+
+use crate::{
+    activity::{Activity, ActivityFeed},
+    models::*,
+    persistance::questions_dao::QuestionDAO,
+    AppState,
+};
+
+use super::{conditional::to_json, inner::HandlerError};
+
+/// Messages sent by clients, e.g. `{"type": "subscribe", "question_id": "..."}`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe { question_id: QuestionId },
+    Unsubscribe { question_id: QuestionId },
+    SubscribeQuestions,
+    UnsubscribeQuestions,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Subscribed {
+        question_id: QuestionId,
+    },
+    Unsubscribed {
+        question_id: QuestionId,
+    },
+    SubscribedQuestions,
+    UnsubscribedQuestions,
+    QuestionCreated {
+        question: Question,
+    },
+    AnswerCreated {
+        answer: Answer,
+    },
+    Viewers {
+        question_id: QuestionId,
+        viewers: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// What a single connection follows. Every followed question counts the connection as one
+/// viewer until it unsubscribes or disconnects.
+#[derive(Default)]
+struct Session {
+    questions: HashSet<QuestionId>,
+    new_questions: bool,
+}
+
+impl Session {
+    async fn handle(
+        &mut self,
+        text: &str,
+        dao: &(dyn QuestionDAO + Send + Sync),
+        activity: &ActivityFeed,
+    ) -> ServerMessage {
+        let message = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(error) => {
+                return ServerMessage::Error {
+                    message: format!("Invalid message: {}", error),
+                }
+            }
+        };
+
+        return match message {
+            ClientMessage::Subscribe { question_id } => {
+                match dao.question_exists(question_id).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        return ServerMessage::Error {
+                            message: format!("No question with id: {}", question_id),
+                        }
+                    }
+                    Err(error) => {
+                        return ServerMessage::Error {
+                            message: ProblemDetails::from(HandlerError::from(error)).detail,
+                        }
+                    }
+                }
+
+                if self.questions.insert(question_id) {
+                    activity.join(question_id);
+                }
+
+                ServerMessage::Subscribed { question_id }
+            }
+            ClientMessage::Unsubscribe { question_id } => {
+                if self.questions.remove(&question_id) {
+                    activity.leave(question_id);
+                }
+
+                ServerMessage::Unsubscribed { question_id }
+            }
+            ClientMessage::SubscribeQuestions => {
+                self.new_questions = true;
+
+                ServerMessage::SubscribedQuestions
+            }
+            ClientMessage::UnsubscribeQuestions => {
+                self.new_questions = false;
+
+                ServerMessage::UnsubscribedQuestions
+            }
+        };
+    }
+
+    /// The message to send for an activity, if this session follows it.
+    fn forward(&self, activity: Activity) -> Option<ServerMessage> {
+        return match activity {
+            Activity::QuestionCreated(question) if self.new_questions => {
+                Some(ServerMessage::QuestionCreated { question })
+            }
+            Activity::AnswerCreated(answer)
+                if self.questions.contains(&answer.detail.question_uuid) =>
+            {
+                Some(ServerMessage::AnswerCreated { answer })
+            }
+            Activity::ViewersChanged {
+                question_id,
+                viewers,
+            } if self.questions.contains(&question_id) => Some(ServerMessage::Viewers {
+                question_id,
+                viewers,
+            }),
+            _ => None,
+        };
+    }
+
+    fn close(self, activity: &ActivityFeed) {
+        for question_id in self.questions {
+            activity.leave(question_id);
+        }
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn socket(State(state): State<AppState>, upgrade: WebSocketUpgrade) -> Response {
+    return upgrade.on_upgrade(move |socket| serve_socket(socket, state));
+}
+
+async fn serve_socket(
+    mut socket: WebSocket,
+    AppState {
+        questions_dao,
+        activity,
+        ..
+    }: AppState,
+) {
+    let mut session = Session::default();
+    let mut activities = Box::pin(activity.subscribe());
+
+    loop {
+        let reply = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    session.handle(&text, questions_dao.as_ref(), &activity).await
+                }
+                Some(Ok(Message::Binary(_))) => ServerMessage::Error {
+                    message: String::from("Only text messages are supported."),
+                },
+                // Pings are answered by axum itself
+                Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+            },
+            Some(activity) = activities.next() => match session.forward(activity) {
+                Some(reply) => reply,
+                None => continue,
+            },
+        };
+
+        if socket.send(Message::Text(to_json(&reply))).await.is_err() {
+            break;
+        }
+    }
+
+    session.close(&activity);
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::persistance::questions_dao::DAO as QuestionsDaoImpl;
+
+    fn answer(question_id: QuestionId) -> Answer {
+        return Answer::new(AnswerFields {
+            question_uuid: question_id,
+            content: "test content".to_owned(),
+        });
+    }
+
+    #[test]
+    fn client_messages_should_be_tagged_by_type() {
+        let question_id = QuestionId(Uuid::new_v4());
+        let text = format!(
+            r#"{{"type": "subscribe", "question_id": "{}"}}"#,
+            question_id
+        );
+
+        let message: ClientMessage = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(message, ClientMessage::Subscribe { question_id });
+    }
+
+    #[test]
+    fn session_should_only_forward_followed_activity() {
+        let followed = QuestionId(Uuid::new_v4());
+        let session = Session {
+            questions: HashSet::from([followed]),
+            new_questions: false,
+        };
+        let followed_answer = answer(followed);
+
+        assert_eq!(
+            session.forward(Activity::AnswerCreated(followed_answer.clone())),
+            Some(ServerMessage::AnswerCreated {
+                answer: followed_answer
+            })
+        );
+        assert_eq!(
+            session.forward(Activity::AnswerCreated(answer(QuestionId(Uuid::new_v4())))),
+            None
+        );
+        assert_eq!(
+            session.forward(Activity::ViewersChanged {
+                question_id: QuestionId(Uuid::new_v4()),
+                viewers: 1
+            }),
+            None
+        );
+    }
+
+    #[sqlx::test]
+    async fn subscribing_should_count_viewers_until_closed(pool: PgPool) {
+        let dao = QuestionsDaoImpl::new(pool);
+        let activity = ActivityFeed::new();
+        let question = dao
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .unwrap();
+        let question_id = question.question_uuid;
+        let text = format!(
+            r#"{{"type": "subscribe", "question_id": "{}"}}"#,
+            question_id
+        );
+        let mut session = Session::default();
+
+        let reply = session.handle(&text, &dao, &activity).await;
+
+        assert_eq!(reply, ServerMessage::Subscribed { question_id });
+        assert_eq!(activity.viewers(question_id), 1);
+
+        session.close(&activity);
+
+        assert_eq!(activity.viewers(question_id), 0);
+    }
+
+    #[sqlx::test]
+    async fn subscribing_to_missing_question_should_fail(pool: PgPool) {
+        let dao = QuestionsDaoImpl::new(pool);
+        let activity = ActivityFeed::new();
+        let question_id = QuestionId(Uuid::new_v4());
+        let text = format!(
+            r#"{{"type": "subscribe", "question_id": "{}"}}"#,
+            question_id
+        );
+        let mut session = Session::default();
+
+        let reply = session.handle(&text, &dao, &activity).await;
+
+        assert_eq!(
+            reply,
+            ServerMessage::Error {
+                message: format!("No question with id: {}", question_id)
+            }
+        );
+        assert_eq!(activity.viewers(question_id), 0);
+    }
+}
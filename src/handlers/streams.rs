@@ -0,0 +1,101 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use serde::Serialize;
+use tokio_stream::{Stream, StreamExt};
+use tracing::instrument;
+
+use crate::{models::*, AppState};
+
+use super::{conditional::to_json, inner::HandlerError};
+
+fn event_stream<T: Serialize>(
+    name: &'static str,
+    items: impl Stream<Item = T> + Send + 'static,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = items.map(move |item| {
+        return Ok(Event::default().event(name).data(to_json(&item)));
+    });
+
+    return Sse::new(events).keep_alive(KeepAlive::default());
+}
+
+#[utoipa::path(
+    get,
+    path = "/questions/stream",
+    tag = "questions",
+    responses(
+        (
+            status = 200,
+            description = "A `question_created` event for every question asked from now on",
+            body = Question,
+            content_type = "text/event-stream"
+        ),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn stream_questions(
+    State(AppState { activity, .. }): State<AppState>,
+) -> impl IntoResponse {
+    return event_stream("question_created", activity.new_questions());
+}
+
+#[utoipa::path(
+    get,
+    path = "/question/{id}/stream",
+    tag = "answers",
+    params(("id" = Uuid, Path, description = "ID of the question to follow")),
+    responses(
+        (
+            status = 200,
+            description = "An `answer_created` event for every answer posted from now on",
+            body = Answer,
+            content_type = "text/event-stream"
+        ),
+        (
+            status = 400,
+            description = "The ID is not a valid UUID",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 404,
+            description = "The question does not exist",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+        (
+            status = 500,
+            description = "Unexpected server error",
+            body = ProblemDetails,
+            content_type = "application/problem+json"
+        ),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
+pub async fn stream_question(
+    State(AppState {
+        questions_dao,
+        activity,
+        ..
+    }): State<AppState>,
+    id: QuestionId,
+) -> Result<impl IntoResponse, HandlerError> {
+    // Subscribe before checking the question exists so no answer posted in between is missed
+    let answers = activity.new_answers(id);
+
+    if !questions_dao.question_exists(id).await? {
+        return Err(HandlerError::NotFound(format!(
+            "No question with id: {}",
+            id
+        )));
+    }
+
+    return Ok(event_stream("answer_created", answers));
+}
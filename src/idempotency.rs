@@ -0,0 +1,23 @@
+use std::{sync::Arc, time::Duration};
+
+use tracing::{error, info};
+
+use crate::persistance::idempotency_dao::IdempotencyDAO;
+
+/// Deletes expired idempotency keys every `period`. Expired keys are already ignored, so this
+/// only keeps the table small.
+pub fn spawn_purger(dao: Arc<dyn IdempotencyDAO + Send + Sync>, period: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            match dao.delete_expired().await {
+                Ok(0) => {}
+                Ok(deleted) => info!(deleted, "Purged expired idempotency keys"),
+                Err(error) => error!(error = ?error, "Could not purge idempotency keys"),
+            }
+        }
+    });
+}
@@ -1,67 +1,228 @@
-extern crate pretty_env_logger;
-#[macro_use]
-extern crate log;
+use std::{net::SocketAddr, num::NonZeroUsize, sync::Arc, time::Duration};
 
-use std::{net::SocketAddr, sync::Arc};
-
-use axum::{
-    routing::{delete, get, post},
-    Router,
-};
+use activity::ActivityFeed;
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, Json};
+use metrics_exporter_prometheus::PrometheusHandle;
 use persistance::{
     answers_dao::{self, AnswerDAO},
+    backup_dao::{self, BackupDAO},
+    cached_dao::{CachedDAO, DaoCache},
+    idempotency_dao,
     questions_dao::{self, QuestionDAO},
+    unit_of_work::{self, UnitOfWorkDAO},
+    webhooks_dao::{self, WebhookDAO},
+};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
 };
-use sqlx::postgres::PgPoolOptions;
 use tokio::net::TcpListener;
+use tower::ServiceBuilder;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::{error, info, Level};
+use utoipa_redoc::{Redoc, Servable};
+use uuid::Uuid;
+use views::ViewCounter;
 
+mod activity;
+mod changes;
+mod dump;
+mod feeds;
 mod handlers;
+mod idempotency;
+mod markdown;
 mod models;
+mod openapi;
 mod persistance;
+mod telemetry;
+mod views;
+mod webhooks;
 
 use handlers::*;
 
 const MAX_CONNECTIONS: u32 = 5;
+const MAX_BODY_SIZE: usize = 64 * 1024;
+/// Repeated views of a question from the same address within this window count once.
+const VIEW_WINDOW: Duration = Duration::from_secs(15 * 60);
+const VIEW_FLUSH_PERIOD: Duration = Duration::from_secs(30);
+const WEBHOOK_POLL_PERIOD: Duration = Duration::from_secs(5);
+const IDEMPOTENCY_PURGE_PERIOD: Duration = Duration::from_secs(60 * 60);
+/// Questions, answers and answer lists each kept in the DAO cache.
+const DAO_CACHE_CAPACITY: NonZeroUsize = NonZeroUsize::new(1000).unwrap();
+const DAO_CACHE_TTL: Duration = Duration::from_secs(60);
 
 #[derive(Clone)]
 pub struct AppState {
     pub questions_dao: Arc<dyn QuestionDAO + Send + Sync>,
     pub answers_dao: Arc<dyn AnswerDAO + Send + Sync>,
+    pub webhooks_dao: Arc<dyn WebhookDAO + Send + Sync>,
+    pub backup_dao: Arc<dyn BackupDAO + Send + Sync>,
+    pub unit_of_work_dao: Arc<dyn UnitOfWorkDAO + Send + Sync>,
+    pub database: PgPool,
+    pub metrics_handle: PrometheusHandle,
+    pub view_counter: Arc<ViewCounter>,
+    pub activity: ActivityFeed,
+    /// Scheme, host and port clients reach the API at, used for absolute links.
+    pub public_url: String,
+    /// Bearer token required by the admin endpoints, which are disabled without one.
+    pub admin_token: Option<String>,
 }
 
 #[tokio::main]
 async fn main() {
-    pretty_env_logger::init();
     // Panic if no .env file exists
     dotenvy::dotenv().unwrap();
+    telemetry::init_tracing();
+    let metrics_handle = telemetry::install_metrics_recorder();
 
     // Panic if DATABASE_URL is not set
     let db_url = dotenvy::var("DATABASE_URL").expect("DATABASE_URL must be set in the .env file");
+    // Tells this instance's own changes apart from those of other instances
+    let origin = format!("stack-overflow-clone {}", Uuid::new_v4());
+    let connect_options = db_url
+        .parse::<PgConnectOptions>()
+        .expect("DATABASE_URL must be a valid connection URL")
+        .application_name(&origin);
     let pool = PgPoolOptions::new()
         .max_connections(MAX_CONNECTIONS)
-        .connect(&db_url)
+        .connect_with(connect_options.clone())
         .await
         .expect("Could not connect to database");
+
+    // `import-posts --site <site> <Posts.xml>` loads a Stack Exchange data dump instead of
+    // serving. Post IDs are only unique per site, so the site is part of every post's ID.
+    let mut args = std::env::args().skip(1);
+
+    if let Some(command) = args.next() {
+        const USAGE: &str = "Usage: import-posts --site <site> <Posts.xml>";
+
+        // Panic on unknown commands rather than start serving by mistake
+        assert_eq!(command, "import-posts", "Unknown command: {}", command);
+        assert_eq!(args.next().as_deref(), Some("--site"), "{}", USAGE);
+        let site = args.next().expect(USAGE);
+        let path = args.next().expect(USAGE);
+
+        return import_posts(pool, &site, &path).await;
+    }
+
     let address = SocketAddr::from(([127, 0, 0, 1], 8000));
+    let public_url = dotenvy::var("PUBLIC_URL").unwrap_or_else(|_| format!("http://{}", address));
     // Panic if the address is already occupied.
     let listener = TcpListener::bind(address).await.unwrap();
-    let app = Router::new()
-        .route("/question/:id", delete(delete_question))
-        .route("/questions", get(read_questions))
-        .route("/question", post(create_question))
-        .route("/answer/:id", delete(delete_answer))
-        .route("/answers/:question_id", get(read_answers))
-        .route("/answer", post(create_answer))
+    let dao_cache = Arc::new(DaoCache::new(DAO_CACHE_CAPACITY, DAO_CACHE_TTL));
+    let questions_dao: Arc<dyn QuestionDAO + Send + Sync> = Arc::new(CachedDAO::new(
+        questions_dao::DAO::new(pool.clone()),
+        dao_cache.clone(),
+    ));
+    let answers_dao: Arc<dyn AnswerDAO + Send + Sync> = Arc::new(CachedDAO::new(
+        answers_dao::DAO::new(pool.clone()),
+        dao_cache.clone(),
+    ));
+    let view_counter = Arc::new(ViewCounter::new(VIEW_WINDOW));
+
+    views::spawn_flusher(
+        view_counter.clone(),
+        questions_dao.clone(),
+        VIEW_FLUSH_PERIOD,
+    );
+
+    let activity = ActivityFeed::new();
+
+    // The listener holds its connection for good, so it gets a pool of its own
+    changes::spawn_listener(
+        PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy_with(connect_options),
+        changes::Subscribers {
+            origin,
+            cache: dao_cache.clone(),
+            activity: activity.clone(),
+            questions_dao: questions_dao.clone(),
+            answers_dao: answers_dao.clone(),
+        },
+    );
+
+    let webhooks_dao: Arc<dyn WebhookDAO + Send + Sync> =
+        Arc::new(webhooks_dao::DAO::new(pool.clone()));
+
+    webhooks::spawn_worker(webhooks_dao.clone(), WEBHOOK_POLL_PERIOD);
+
+    idempotency::spawn_purger(
+        Arc::new(idempotency_dao::DAO::new(pool.clone())),
+        IDEMPOTENCY_PURGE_PERIOD,
+    );
+
+    let (api, api_doc) = openapi::router();
+    let app = api
+        .route("/metrics", get(read_metrics))
+        .route("/assets/highlight.css", get(read_highlight_css))
+        .route("/ws", get(socket))
+        .route_layer(middleware::from_fn(telemetry::track_requests))
+        .merge(Redoc::with_url("/docs", api_doc.clone()))
+        .route("/openapi.json", get(|| async { Json(api_doc) }))
+        .fallback(fallback)
+        .layer(DefaultBodyLimit::max(MAX_BODY_SIZE))
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::new(
+                    telemetry::REQUEST_ID_HEADER,
+                    MakeRequestUuid,
+                ))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(telemetry::make_request_span)
+                        .on_response(DefaultOnResponse::new().level(Level::INFO)),
+                )
+                .layer(PropagateRequestIdLayer::new(telemetry::REQUEST_ID_HEADER))
+                .layer(middleware::from_fn(telemetry::scope_request_id)),
+        )
         .with_state(AppState {
-            questions_dao: Arc::new(questions_dao::DAO::new(pool.clone())),
-            answers_dao: Arc::new(answers_dao::DAO::new(pool.clone())),
+            questions_dao,
+            answers_dao,
+            webhooks_dao,
+            backup_dao: Arc::new(backup_dao::DAO::new(pool.clone())),
+            unit_of_work_dao: Arc::new(unit_of_work::DAO::new(pool.clone(), dao_cache)),
+            database: pool,
+            metrics_handle,
+            view_counter,
+            activity,
+            public_url: public_url.trim_end_matches('/').to_owned(),
+            admin_token: dotenvy::var("ADMIN_TOKEN").ok(),
         });
 
     info!(
         "Axum Server Running at: http://{:?}",
         listener.local_addr().unwrap()
     );
-    axum::serve(listener, app.into_make_service())
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+async fn import_posts(pool: PgPool, site: &str, path: &str) {
+    // Panic if the dump cannot be read
+    let file = tokio::fs::File::open(path)
         .await
-        .unwrap();
+        .expect("Could not open the posts file");
+    let dao = backup_dao::DAO::new(pool);
+
+    match dump::import_posts(tokio::io::BufReader::new(file), site, &dao).await {
+        Ok(progress) => info!(
+            rows = progress.rows,
+            questions = progress.questions,
+            answers = progress.answers,
+            skipped = progress.skipped,
+            "Imported the posts"
+        ),
+        Err(error) => {
+            error!(error = %error, "Importing the posts failed");
+            std::process::exit(1);
+        }
+    }
 }
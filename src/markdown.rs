@@ -0,0 +1,212 @@
+use std::{borrow::Cow, sync::LazyLock};
+
+use pulldown_cmark::{html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use syntect::{
+    highlighting::ThemeSet,
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// Every class emitted by the highlighter starts with this prefix, which is also what the
+/// sanitizer uses to tell highlighter markup apart from user supplied classes.
+const CLASS_PREFIX: &str = "hl-";
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// Renders user supplied markdown (CommonMark plus tables, strikethrough and task lists) into
+/// HTML that is safe to embed in a page. Fenced code blocks are syntax highlighted.
+pub fn render(source: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut unsafe_html = String::with_capacity(source.len() * 3 / 2);
+
+    html::push_html(
+        &mut unsafe_html,
+        highlight_code_blocks(Parser::new_ext(source, options)).into_iter(),
+    );
+
+    return sanitize(&unsafe_html);
+}
+
+/// Stylesheet matching the classes emitted for highlighted code blocks.
+pub fn highlight_css() -> String {
+    let themes = ThemeSet::load_defaults();
+
+    // Panic if the bundled theme is missing or cannot be converted
+    return css_for_theme_with_class_style(&themes.themes[HIGHLIGHT_THEME], CLASS_STYLE)
+        .expect("The bundled highlight theme should convert to CSS");
+}
+
+/// Replaces every code block in a known language with pre-rendered highlighted markup, leaving
+/// other events as is.
+fn highlight_code_blocks<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut output = Vec::new();
+    let mut code_block: Option<(CodeBlockKind<'a>, String)> = None;
+
+    for event in events {
+        match (event, &mut code_block) {
+            (Event::Start(Tag::CodeBlock(kind)), None) => code_block = Some((kind, String::new())),
+            (Event::Text(text), Some((_, code))) => code.push_str(&text),
+            (Event::End(TagEnd::CodeBlock), Some(_)) => {
+                let (kind, code) = code_block.take().unwrap();
+                let info = match &kind {
+                    CodeBlockKind::Fenced(info) => info.as_ref(),
+                    CodeBlockKind::Indented => "",
+                };
+
+                match highlight(info, &code) {
+                    Some(html) => output.push(Event::Html(html.into())),
+                    None => output.extend([
+                        Event::Start(Tag::CodeBlock(kind)),
+                        Event::Text(code.into()),
+                        Event::End(TagEnd::CodeBlock),
+                    ]),
+                }
+            }
+            (event, _) => output.push(event),
+        }
+    }
+
+    return output;
+}
+
+/// Resolves the syntax of a code block together with the name used for its `language-` class.
+fn find_syntax<'a>(info: &'a str, code: &str) -> Option<(&'static SyntaxReference, &'a str)> {
+    let language = info.split_whitespace().next().unwrap_or_default();
+
+    if !language.is_empty() {
+        if let Some(syntax) = SYNTAXES.find_syntax_by_token(language) {
+            return Some((syntax, language));
+        }
+    }
+
+    // Fall back to shebangs and other first line markers when no usable language is given
+    let syntax = SYNTAXES.find_syntax_by_first_line(code)?;
+    let name = syntax.file_extensions.first().unwrap_or(&syntax.name);
+
+    return Some((syntax, name));
+}
+
+fn highlight(info: &str, code: &str) -> Option<String> {
+    let (syntax, language) = find_syntax(info, code)?;
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAXES, CLASS_STYLE);
+
+    for line in LinesWithEndings::from(code) {
+        generator
+            .parse_html_for_line_which_includes_newline(line)
+            .ok()?;
+    }
+
+    // The language comes from user input, so only keep characters that are safe in a class name
+    let language: String = language
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '+' | '#' | '-' => c,
+            _ => '-',
+        })
+        .collect();
+
+    return Some(format!(
+        "<pre><code class=\"{}code language-{}\">{}</code></pre>\n",
+        CLASS_PREFIX,
+        language,
+        generator.finalize()
+    ));
+}
+
+/// Allowlist based sanitizer: anything ammonia does not explicitly allow (scripts, event
+/// handlers, `javascript:` URLs, inline styles...) is dropped. Only highlighter and language
+/// classes survive on code markup.
+fn sanitize(unsafe_html: &str) -> String {
+    return ammonia::Builder::default()
+        .add_generic_attributes(["align"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("code", ["class"])
+        .add_tag_attributes("span", ["class"])
+        .add_tags(["input"])
+        .attribute_filter(|_, attribute, value| {
+            if attribute != "class" {
+                return Some(Cow::Borrowed(value));
+            }
+
+            let classes: Vec<&str> = value
+                .split_whitespace()
+                .filter(|class| class.starts_with(CLASS_PREFIX) || class.starts_with("language-"))
+                .collect();
+
+            return (!classes.is_empty()).then(|| Cow::Owned(classes.join(" ")));
+        })
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .clean(unsafe_html)
+        .to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_should_support_tables_and_fenced_code() {
+        let html = render("| a | b |\n|---|---|\n| 1 | 2 |\n\n```rust\nfn main() {}\n```");
+
+        assert!(html.contains("<table>"));
+        assert!(html.contains("<td>1</td>"));
+        assert!(html.contains("<pre><code"));
+        assert!(html.contains("main"));
+    }
+
+    #[test]
+    fn render_should_strip_scripts_and_event_handlers() {
+        let html = render("<script>alert(1)</script><img src=\"x.png\" onerror=\"alert(1)\">");
+
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+        assert!(html.contains("<img src=\"x.png\">"));
+    }
+
+    #[test]
+    fn render_should_strip_javascript_links() {
+        let html = render("[click](javascript:alert(1))");
+
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("rel=\"nofollow noopener noreferrer\""));
+    }
+
+    #[test]
+    fn render_should_highlight_fenced_code_by_language() {
+        let html = render("```rust\nfn main() {}\n```");
+
+        assert!(html.contains("<code class=\"hl-code language-rust\">"));
+        assert!(html.contains("<span class=\"hl-storage hl-type hl-function hl-rust\">fn</span>"));
+    }
+
+    #[test]
+    fn render_should_detect_language_from_shebang() {
+        let html = render("```\n#!/bin/bash\necho hi\n```");
+
+        assert!(html.contains("<code class=\"hl-code language-sh\">"));
+    }
+
+    #[test]
+    fn render_should_escape_unknown_languages() {
+        let html = render("```nonexistent\n<b>bold</b>\n```");
+
+        assert_eq!(
+            html,
+            "<pre><code class=\"language-nonexistent\">&lt;b&gt;bold&lt;/b&gt;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn render_should_drop_user_supplied_classes() {
+        let html = render("<span class=\"evil hl-keyword\">x</span>");
+
+        assert_eq!(html, "<p><span class=\"hl-keyword\">x</span></p>\n");
+    }
+}
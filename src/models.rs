@@ -1,48 +1,358 @@
+use std::{fmt, str::FromStr};
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::markdown;
+
+const MAX_REPEATED_CHARACTERS: usize = 4;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema)]
+#[serde(transparent)]
+pub struct QuestionId(pub Uuid);
+
+impl fmt::Display for QuestionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.0.fmt(f);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema)]
+#[serde(transparent)]
+pub struct AnswerId(pub Uuid);
+
+impl fmt::Display for AnswerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.0.fmt(f);
+    }
+}
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema)]
+#[serde(transparent)]
+pub struct WebhookId(pub Uuid);
+
+impl fmt::Display for WebhookId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.0.fmt(f);
+    }
+}
+
+fn trimmed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    return Ok(String::deserialize(deserializer)?.trim().to_owned());
+}
+
+fn validate_title(title: &str) -> Result<(), ValidationError> {
+    if !title.chars().any(char::is_alphabetic) {
+        return Err(ValidationError::new("title_without_letters")
+            .with_message("must contain at least one letter".into()));
+    }
+
+    let letters: Vec<char> = title.chars().filter(|c| c.is_alphabetic()).collect();
+
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        return Err(ValidationError::new("title_all_caps")
+            .with_message("must not be written in all capital letters".into()));
+    }
+
+    let mut run = 0;
+    let mut previous = None;
+
+    for c in title.chars() {
+        run = if Some(c) == previous { run + 1 } else { 1 };
+        previous = Some(c);
+
+        if run > MAX_REPEATED_CHARACTERS {
+            return Err(ValidationError::new("title_repeated_characters")
+                .with_message("must not repeat the same character over and over".into()));
+        }
+    }
+
+    return Ok(());
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema, Validate)]
 pub struct QuestionFields {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(
+        length(min = 15, max = 150, message = "must be between 15 and 150 characters"),
+        custom(function = "validate_title")
+    )]
     pub title: String,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(
+        min = 30,
+        max = 30000,
+        message = "must be between 30 and 30000 characters"
+    ))]
     pub description: String,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
 pub struct Question {
-    pub question_uuid: Uuid,
+    pub question_uuid: QuestionId,
     pub detail: QuestionFields,
+    /// Sanitized HTML rendering of the markdown `detail.description`.
+    pub description_html: String,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema, Validate)]
 pub struct AnswerFields {
-    pub question_uuid: Uuid,
+    pub question_uuid: QuestionId,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(
+        min = 30,
+        max = 30000,
+        message = "must be between 30 and 30000 characters"
+    ))]
     pub content: String,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
 pub struct Answer {
-    pub answer_uuid: Uuid,
+    pub answer_uuid: AnswerId,
     pub detail: AnswerFields,
+    /// Sanitized HTML rendering of the markdown `detail.content`.
+    pub content_html: String,
     pub created_at: DateTime<Utc>,
 }
 
 impl Answer {
     pub fn new(detail: AnswerFields) -> Self {
         Self {
-            answer_uuid: Uuid::new_v4(),
+            answer_uuid: AnswerId(Uuid::new_v4()),
+            content_html: markdown::render(&detail.content),
             detail,
             created_at: chrono::offset::Utc::now(),
         }
     }
 }
 
+/// A question together with every answer to it, oldest answer first.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct QuestionDetail {
+    #[serde(flatten)]
+    pub question: Question,
+    pub answers: Vec<Answer>,
+    pub answer_count: usize,
+    pub view_count: i64,
+}
+
+impl QuestionDetail {
+    /// What only changes when the question or its answers are written. The view count is left
+    /// out, because every read changes it.
+    pub fn content(&self) -> (&Question, &[Answer]) {
+        return (&self.question, &self.answers);
+    }
+}
+
+/// A question asked together with the asker's own answer to it, which are stored together or
+/// not at all.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema, Validate)]
+pub struct SelfAnsweredQuestionFields {
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub question: QuestionFields,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(
+        min = 30,
+        max = 30000,
+        message = "must be between 30 and 30000 characters"
+    ))]
+    pub answer: String,
+}
+
+/// One line of a JSON Lines export. Questions are written before the answers to them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExportRecord {
+    Question(Question),
+    Answer(Answer),
+}
+
+impl Validate for ExportRecord {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        return match self {
+            Self::Question(question) => question.detail.validate(),
+            Self::Answer(answer) => answer.detail.validate(),
+        };
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ImportSummary {
+    pub questions: usize,
+    pub answers: usize,
+    /// When set, every record was checked against the database but nothing was kept.
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, ToSchema)]
+pub enum WebhookEvent {
+    #[serde(rename = "question.created")]
+    QuestionCreated,
+    #[serde(rename = "question.deleted")]
+    QuestionDeleted,
+    #[serde(rename = "answer.created")]
+    AnswerCreated,
+    #[serde(rename = "answer.deleted")]
+    AnswerDeleted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Self::QuestionCreated => "question.created",
+            Self::QuestionDeleted => "question.deleted",
+            Self::AnswerCreated => "answer.created",
+            Self::AnswerDeleted => "answer.deleted",
+        };
+    }
+}
+
+impl FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(event: &str) -> Result<Self, Self::Err> {
+        return match event {
+            "question.created" => Ok(Self::QuestionCreated),
+            "question.deleted" => Ok(Self::QuestionDeleted),
+            "answer.created" => Ok(Self::AnswerCreated),
+            "answer.deleted" => Ok(Self::AnswerDeleted),
+            _ => Err(format!("Unknown webhook event: {}", event)),
+        };
+    }
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), ValidationError> {
+    let valid = reqwest::Url::parse(url)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host());
+
+    if !valid {
+        return Err(ValidationError::new("webhook_url")
+            .with_message("must be an absolute http or https URL".into()));
+    }
+
+    return Ok(());
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, ToSchema, Validate)]
+pub struct WebhookFields {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(custom(function = "validate_webhook_url"))]
+    pub url: String,
+    #[validate(length(min = 1, message = "must contain at least one event"))]
+    pub events: Vec<WebhookEvent>,
+    /// Key of the HMAC-SHA256 signature sent with every delivery. Never returned.
+    #[validate(length(min = 16, max = 256, message = "must be between 16 and 256 characters"))]
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct Webhook {
+    pub webhook_uuid: WebhookId,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Every attempt failed; the delivery will not be retried.
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Dead => "dead",
+        };
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Self, Self::Err> {
+        return match status {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "dead" => Ok(Self::Dead),
+            _ => Err(format!("Unknown delivery status: {}", status)),
+        };
+    }
+}
+
+/// Log entry of one event sent to a webhook, including the outcome of its latest attempt.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub delivery_uuid: Uuid,
+    pub event: WebhookEvent,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// HTTP status of the latest attempt, if the receiver responded at all.
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed by the worker, together with where and how to send it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DueDelivery {
+    pub delivery_uuid: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// Attempts so far, including the one the delivery was claimed for.
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// RFC 7807 problem details body returned by every failed request.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
 #[derive(Error, Debug)]
 pub enum DBError {
-    #[error("Invalid UUID provided: {0}")]
-    InvalidUUID(String),
+    #[error("Resource not found: {0}")]
+    NotFound(String),
+    #[error("Referenced resource does not exist: {0}")]
+    ForeignKeyViolation(String),
+    #[error("Resource already exists: {0}")]
+    UniqueViolation(String),
+    #[error("Constraint check failed: {0}")]
+    CheckViolation(String),
+    #[error("Concurrent update conflict: {0}")]
+    SerializationFailure(String),
     #[error("Database error occurred")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
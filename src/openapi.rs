@@ -0,0 +1,191 @@
+use axum::Router;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{handlers::*, AppState};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Stack Overflow Clone", description = "Questions and answers API"),
+    tags(
+        (name = "questions", description = "Asking and removing questions"),
+        (name = "answers", description = "Answering questions"),
+        (name = "feeds", description = "Atom and RSS feeds for feed readers"),
+        (name = "webhooks", description = "Signed push notifications to other services"),
+        (name = "admin", description = "Backups, restricted to holders of the admin token")
+    ),
+    modifiers(&AdminToken)
+)]
+pub struct ApiDoc;
+
+/// Registers the bearer token the admin endpoints require.
+struct AdminToken;
+
+impl Modify for AdminToken {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let scheme = HttpBuilder::new()
+            .scheme(HttpAuthScheme::Bearer)
+            .description(Some("The `ADMIN_TOKEN` the server was started with"))
+            .build();
+
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme("admin_token", SecurityScheme::Http(scheme));
+    }
+}
+
+/// Builds the documented API routes together with the OpenAPI document describing them, so
+/// the two cannot be registered separately.
+pub fn router() -> (Router<AppState>, utoipa::openapi::OpenApi) {
+    return OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(read_question, delete_question))
+        .routes(routes!(read_questions))
+        .routes(routes!(stream_questions))
+        .routes(routes!(create_question))
+        .routes(routes!(create_self_answered_question))
+        .routes(routes!(read_answer, delete_answer))
+        .routes(routes!(read_answers))
+        .routes(routes!(stream_question))
+        .routes(routes!(read_questions_atom))
+        .routes(routes!(read_questions_rss))
+        .routes(routes!(read_answers_atom))
+        .routes(routes!(read_answers_rss))
+        .routes(routes!(create_answer))
+        .routes(routes!(create_webhook))
+        .routes(routes!(read_webhooks))
+        .routes(routes!(delete_webhook))
+        .routes(routes!(read_deliveries))
+        .routes(routes!(export_data))
+        .routes(routes!(import_data))
+        .split_for_parts();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+    use axum::{
+        body::Body,
+        http::{Method, Request, StatusCode},
+    };
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use sqlx::postgres::PgPoolOptions;
+    use tower::ServiceExt;
+    use utoipa::openapi::path::ParameterIn;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        activity::ActivityFeed,
+        persistance::{
+            answers_dao, backup_dao, cached_dao::DaoCache, questions_dao, unit_of_work,
+            webhooks_dao,
+        },
+        views::ViewCounter,
+    };
+
+    fn unreachable_database_state() -> AppState {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .unwrap();
+
+        return AppState {
+            questions_dao: Arc::new(questions_dao::DAO::new(pool.clone())),
+            answers_dao: Arc::new(answers_dao::DAO::new(pool.clone())),
+            webhooks_dao: Arc::new(webhooks_dao::DAO::new(pool.clone())),
+            backup_dao: Arc::new(backup_dao::DAO::new(pool.clone())),
+            unit_of_work_dao: Arc::new(unit_of_work::DAO::new(
+                pool.clone(),
+                Arc::new(DaoCache::new(NonZeroUsize::MIN, Duration::ZERO)),
+            )),
+            database: pool,
+            metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+            view_counter: Arc::new(ViewCounter::new(Duration::from_secs(60))),
+            activity: ActivityFeed::new(),
+            public_url: "http://localhost".to_owned(),
+            admin_token: None,
+        };
+    }
+
+    fn concrete_path(template: &str) -> String {
+        return template
+            .split('/')
+            .map(|segment| {
+                if segment.starts_with('{') && segment.ends_with('}') {
+                    return Uuid::new_v4().to_string();
+                }
+
+                return segment.to_owned();
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+    }
+
+    #[tokio::test]
+    async fn every_documented_operation_should_be_routed() {
+        let (router, openapi) = router();
+        let router = router.with_state(unreachable_database_state());
+
+        assert!(!openapi.paths.paths.is_empty());
+
+        for (template, item) in openapi.paths.paths.iter() {
+            let operations = [
+                (Method::GET, item.get.is_some()),
+                (Method::POST, item.post.is_some()),
+                (Method::PUT, item.put.is_some()),
+                (Method::PATCH, item.patch.is_some()),
+                (Method::DELETE, item.delete.is_some()),
+            ];
+
+            for (method, documented) in operations {
+                let request = Request::builder()
+                    .method(method.clone())
+                    .uri(concrete_path(template))
+                    .header("content-type", "application/json")
+                    .body(Body::from("{}"))
+                    .unwrap();
+
+                let status = router.clone().oneshot(request).await.unwrap().status();
+                let routed =
+                    status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED;
+
+                assert_eq!(
+                    routed, documented,
+                    "{} {} is documented: {} but routed: {} ({})",
+                    method, template, documented, routed, status
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_path_parameter_should_be_documented() {
+        let (_, openapi) = router();
+
+        for (template, item) in openapi.paths.paths.iter() {
+            let expected: Vec<&str> = template
+                .split('/')
+                .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+                .collect();
+
+            let operations = [&item.get, &item.post, &item.delete];
+
+            for operation in operations.into_iter().flatten() {
+                let documented: Vec<&str> = operation
+                    .parameters
+                    .iter()
+                    .flatten()
+                    .filter(|parameter| parameter.parameter_in == ParameterIn::Path)
+                    .map(|parameter| parameter.name.as_str())
+                    .collect();
+
+                assert_eq!(documented, expected, "Parameters of {}", template);
+            }
+        }
+    }
+}
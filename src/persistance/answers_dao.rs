@@ -1,98 +1,170 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use sqlx::PgPool;
-use uuid::Uuid;
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::{markdown, models::*, telemetry::QueryTimer};
 
-use crate::models::*;
+use super::{rows::AnswerRow, unit_of_work::Database};
 
 #[async_trait]
 pub trait AnswerDAO {
     async fn create_answer(&self, details: AnswerFields) -> Result<Answer, DBError>;
-    async fn delete_answer(&self, id: Uuid) -> Result<(), DBError>;
-    async fn get_answers(&self, question_id: Uuid) -> Result<Vec<Answer>, DBError>;
+    async fn delete_answer(&self, id: AnswerId) -> Result<(), DBError>;
+    async fn get_answer(&self, id: AnswerId) -> Result<Answer, DBError>;
+    /// Locks the answer against changes by others until the unit of work it runs in ends.
+    async fn lock_answer(&self, id: AnswerId) -> Result<(), DBError>;
+    /// Every answer to the question, oldest first.
+    async fn get_answers(&self, question_id: QuestionId) -> Result<Vec<Answer>, DBError>;
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct DAO {
-    database: PgPool,
+    database: Database,
 }
 
 impl DAO {
     pub fn new(database: PgPool) -> Self {
-        return Self { database };
+        return Self {
+            database: Database::Pool(database),
+        };
+    }
+
+    pub fn in_transaction(transaction: Arc<Mutex<Transaction<'static, Postgres>>>) -> Self {
+        return Self {
+            database: Database::Transaction(transaction),
+        };
     }
 }
 
 #[async_trait]
 impl AnswerDAO for DAO {
+    #[instrument(skip(self, details))]
     async fn create_answer(&self, details: AnswerFields) -> Result<Answer, DBError> {
-        if details.question_uuid.is_nil() {
-            return Err(DBError::InvalidUUID(format!(
-                "Invalid question_id: {}",
-                details.question_uuid
-            )));
-        }
+        let content_html = markdown::render(&details.content);
+
+        let _timer = QueryTimer::start("answers", "create_answer");
 
         let record = sqlx::query!(
             r#"
-            INSERT INTO answers (content, question_id)
-            VALUES ($1, $2)
+            INSERT INTO answers (content, content_html, question_id)
+            VALUES ($1, $2, $3)
             RETURNING *;
         "#,
             details.content,
-            details.question_uuid
+            content_html,
+            details.question_uuid.0
         )
-        .fetch_one(&self.database)
+        .fetch_one(&mut *self.database.acquire().await?)
         .await
-        .map_err(|e| DBError::Other(Box::new(e)))?;
+        .map_err(|e| {
+            return match DBError::from(e) {
+                DBError::ForeignKeyViolation(_) => DBError::ForeignKeyViolation(format!(
+                    "No question with id: {}",
+                    details.question_uuid
+                )),
+                error => error,
+            };
+        })?;
 
         return Ok(Answer {
-            answer_uuid: record.id,
+            answer_uuid: AnswerId(record.id),
             detail: AnswerFields {
-                question_uuid: record.question_id,
+                question_uuid: QuestionId(record.question_id),
                 content: record.content,
             },
+            content_html,
             created_at: record.created_at,
         });
     }
 
-    async fn delete_answer(&self, id: Uuid) -> Result<(), DBError> {
-        if id.is_nil() {
-            return Err(DBError::InvalidUUID(format!(
-                "Invalid answer id provided: {}",
-                id
-            )));
+    #[instrument(skip(self))]
+    async fn delete_answer(&self, id: AnswerId) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("answers", "delete_answer");
+
+        let result = sqlx::query!("DELETE FROM answers WHERE id = $1", id.0)
+            .execute(&mut *self.database.acquire().await?)
+            .await
+            .map_err(DBError::from)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DBError::NotFound(format!("No answer with id: {}", id)));
         }
 
-        sqlx::query!("DELETE FROM answers WHERE id = $1", id)
-            .execute(&self.database)
+        return Ok(());
+    }
+
+    #[instrument(skip(self))]
+    async fn get_answer(&self, id: AnswerId) -> Result<Answer, DBError> {
+        let _timer = QueryTimer::start("answers", "get_answer");
+
+        let row = sqlx::query_as!(
+            AnswerRow,
+            r#"
+                SELECT id, question_id, content, content_html, created_at FROM answers
+                WHERE id = $1
+            "#,
+            id.0
+        )
+        .fetch_optional(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?
+        .ok_or_else(|| DBError::NotFound(format!("No answer with id: {}", id)))?;
+
+        return Ok(Answer::from(row));
+    }
+
+    #[instrument(skip(self))]
+    async fn lock_answer(&self, id: AnswerId) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("answers", "lock_answer");
+
+        let locked = sqlx::query!("SELECT id FROM answers WHERE id = $1 FOR UPDATE", id.0)
+            .fetch_optional(&mut *self.database.acquire().await?)
             .await
-            .map_err(|e| DBError::Other(Box::new(e)))?;
+            .map_err(DBError::from)?;
+
+        if locked.is_none() {
+            return Err(DBError::NotFound(format!("No answer with id: {}", id)));
+        }
 
         return Ok(());
     }
 
-    async fn get_answers(&self, question_id: Uuid) -> Result<Vec<Answer>, DBError> {
-        if question_id.is_nil() {
-            return Err(DBError::InvalidUUID(format!(
-                "Invalid question_id: {}",
+    #[instrument(skip(self))]
+    async fn get_answers(&self, question_id: QuestionId) -> Result<Vec<Answer>, DBError> {
+        let _timer = QueryTimer::start("answers", "get_answers");
+
+        let question_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM questions WHERE id = $1) AS "exists!""#,
+            question_id.0
+        )
+        .fetch_one(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        if !question_exists {
+            return Err(DBError::NotFound(format!(
+                "No question with id: {}",
                 question_id
             )));
         }
 
-        let records = sqlx::query!("SELECT * FROM answers WHERE question_id = $1;", question_id)
-            .fetch_all(&self.database)
-            .await
-            .map_err(|e| DBError::Other(Box::new(e)))?;
-
-        return Ok(records
-            .into_iter()
-            .map(|record| Answer {
-                answer_uuid: record.id,
-                detail: AnswerFields {
-                    content: record.content,
-                    question_uuid: record.question_id,
-                },
-                created_at: record.created_at,
-            })
-            .collect());
+        return Ok(sqlx::query_as!(
+            AnswerRow,
+            r#"
+                SELECT id, question_id, content, content_html, created_at FROM answers
+                WHERE question_id = $1
+                ORDER BY created_at, id
+            "#,
+            question_id.0
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?
+        .into_iter()
+        .map(Answer::from)
+        .collect());
     }
 }
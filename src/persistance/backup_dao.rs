@@ -0,0 +1,353 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{markdown, models::*, telemetry::QueryTimer};
+
+use super::rows::{AnswerRow, QuestionRow};
+
+/// Records read ahead of a slow export client.
+const EXPORT_BUFFER: usize = 64;
+
+pub type RecordStream = Pin<Box<dyn Stream<Item = Result<ExportRecord, DBError>> + Send>>;
+
+#[async_trait]
+pub trait BackupDAO {
+    /// Every question followed by every answer, read from a single snapshot.
+    fn export(&self) -> RecordStream;
+    /// Stores the records with their original IDs and timestamps, all or nothing. A dry run
+    /// performs every insert and then rolls them back.
+    async fn import(
+        &self,
+        records: Vec<ExportRecord>,
+        dry_run: bool,
+    ) -> Result<ImportSummary, DBError>;
+    /// Stores the records that are not stored yet and skips answers to unknown questions, so
+    /// an interrupted import can be run again. Only the stored records are counted.
+    async fn merge(&self, records: Vec<ExportRecord>) -> Result<ImportSummary, DBError>;
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct DAO {
+    database: PgPool,
+}
+
+impl DAO {
+    pub fn new(database: PgPool) -> Self {
+        return Self { database };
+    }
+}
+
+async fn export_snapshot(
+    database: PgPool,
+    sender: &mpsc::Sender<Result<ExportRecord, DBError>>,
+) -> Result<(), DBError> {
+    let mut transaction = database.begin().await.map_err(DBError::from)?;
+
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *transaction)
+        .await
+        .map_err(DBError::from)?;
+
+    let mut questions = sqlx::query_as!(
+        QuestionRow,
+        r#"
+            SELECT id, title, description, description_html, created_at FROM questions
+            ORDER BY created_at, id
+        "#
+    )
+    .fetch(&mut *transaction);
+
+    while let Some(row) = questions.next().await {
+        let question = Question::from(row.map_err(DBError::from)?);
+
+        // The client went away, so there is nobody left to export to
+        if sender
+            .send(Ok(ExportRecord::Question(question)))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+
+    drop(questions);
+
+    let mut answers = sqlx::query_as!(
+        AnswerRow,
+        r#"
+            SELECT id, question_id, content, content_html, created_at FROM answers
+            ORDER BY created_at, id
+        "#
+    )
+    .fetch(&mut *transaction);
+
+    while let Some(row) = answers.next().await {
+        let answer = Answer::from(row.map_err(DBError::from)?);
+
+        if sender.send(Ok(ExportRecord::Answer(answer))).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    return Ok(());
+}
+
+/// Questions as one array per column, to be inserted with `UNNEST`.
+struct QuestionColumns {
+    ids: Vec<Uuid>,
+    titles: Vec<String>,
+    descriptions: Vec<String>,
+    descriptions_html: Vec<String>,
+    created_at: Vec<DateTime<Utc>>,
+}
+
+impl QuestionColumns {
+    fn new(questions: Vec<Question>) -> Self {
+        let mut columns = Self {
+            ids: Vec::with_capacity(questions.len()),
+            titles: Vec::with_capacity(questions.len()),
+            descriptions: Vec::with_capacity(questions.len()),
+            descriptions_html: Vec::with_capacity(questions.len()),
+            created_at: Vec::with_capacity(questions.len()),
+        };
+
+        for question in questions {
+            columns.ids.push(question.question_uuid.0);
+            columns.titles.push(question.detail.title);
+            // Imported HTML is not trusted; it is rendered again from the markdown
+            columns
+                .descriptions_html
+                .push(markdown::render(&question.detail.description));
+            columns.descriptions.push(question.detail.description);
+            columns.created_at.push(question.created_at);
+        }
+
+        return columns;
+    }
+}
+
+/// Answers as one array per column, to be inserted with `UNNEST`.
+struct AnswerColumns {
+    ids: Vec<Uuid>,
+    question_ids: Vec<Uuid>,
+    contents: Vec<String>,
+    contents_html: Vec<String>,
+    created_at: Vec<DateTime<Utc>>,
+}
+
+impl AnswerColumns {
+    fn new(answers: Vec<Answer>) -> Self {
+        let mut columns = Self {
+            ids: Vec::with_capacity(answers.len()),
+            question_ids: Vec::with_capacity(answers.len()),
+            contents: Vec::with_capacity(answers.len()),
+            contents_html: Vec::with_capacity(answers.len()),
+            created_at: Vec::with_capacity(answers.len()),
+        };
+
+        for answer in answers {
+            columns.ids.push(answer.answer_uuid.0);
+            columns.question_ids.push(answer.detail.question_uuid.0);
+            columns
+                .contents_html
+                .push(markdown::render(&answer.detail.content));
+            columns.contents.push(answer.detail.content);
+            columns.created_at.push(answer.created_at);
+        }
+
+        return columns;
+    }
+}
+
+fn split(records: Vec<ExportRecord>) -> (Vec<Question>, Vec<Answer>) {
+    let mut questions = Vec::new();
+    let mut answers = Vec::new();
+
+    for record in records {
+        match record {
+            ExportRecord::Question(question) => questions.push(question),
+            ExportRecord::Answer(answer) => answers.push(answer),
+        }
+    }
+
+    return (questions, answers);
+}
+
+/// Tells the `notify_post_change` trigger that the transaction writes posts in bulk, so instances
+/// clear their caches once instead of evicting and relaying every post.
+async fn mark_bulk_write(transaction: &mut Transaction<'_, Postgres>) -> Result<(), DBError> {
+    sqlx::query!("SET LOCAL app.bulk_write = 'on'")
+        .execute(&mut **transaction)
+        .await
+        .map_err(DBError::from)?;
+
+    return Ok(());
+}
+
+async fn insert_questions(
+    transaction: &mut Transaction<'_, Postgres>,
+    questions: Vec<Question>,
+) -> Result<(), DBError> {
+    let columns = QuestionColumns::new(questions);
+
+    sqlx::query!(
+        r#"
+            INSERT INTO questions (id, title, description, description_html, created_at)
+            SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[], $5::timestamptz[])
+        "#,
+        &columns.ids,
+        &columns.titles,
+        &columns.descriptions,
+        &columns.descriptions_html,
+        &columns.created_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(DBError::from)?;
+
+    return Ok(());
+}
+
+async fn insert_answers(
+    transaction: &mut Transaction<'_, Postgres>,
+    answers: Vec<Answer>,
+) -> Result<(), DBError> {
+    let columns = AnswerColumns::new(answers);
+
+    sqlx::query!(
+        r#"
+            INSERT INTO answers (id, question_id, content, content_html, created_at)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::text[], $5::timestamptz[])
+        "#,
+        &columns.ids,
+        &columns.question_ids,
+        &columns.contents,
+        &columns.contents_html,
+        &columns.created_at
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| {
+        return match DBError::from(e) {
+            DBError::ForeignKeyViolation(_) => DBError::ForeignKeyViolation(String::from(
+                "An answer belongs to a question that is neither imported nor stored",
+            )),
+            error => error,
+        };
+    })?;
+
+    return Ok(());
+}
+
+#[async_trait]
+impl BackupDAO for DAO {
+    fn export(&self) -> RecordStream {
+        let database = self.database.clone();
+        let (sender, receiver) = mpsc::channel(EXPORT_BUFFER);
+
+        tokio::spawn(async move {
+            let _timer = QueryTimer::start("backup", "export");
+
+            if let Err(error) = export_snapshot(database, &sender).await {
+                error!(error = ?error, "Export failed");
+                let _ = sender.send(Err(error)).await;
+            }
+        });
+
+        return Box::pin(ReceiverStream::new(receiver));
+    }
+
+    #[instrument(skip(self, records), fields(records = records.len()))]
+    async fn import(
+        &self,
+        records: Vec<ExportRecord>,
+        dry_run: bool,
+    ) -> Result<ImportSummary, DBError> {
+        let _timer = QueryTimer::start("backup", "import");
+
+        let (questions, answers) = split(records);
+
+        let summary = ImportSummary {
+            questions: questions.len(),
+            answers: answers.len(),
+            dry_run,
+        };
+
+        let mut transaction = self.database.begin().await.map_err(DBError::from)?;
+
+        mark_bulk_write(&mut transaction).await?;
+
+        insert_questions(&mut transaction, questions).await?;
+        insert_answers(&mut transaction, answers).await?;
+
+        if dry_run {
+            transaction.rollback().await.map_err(DBError::from)?;
+        } else {
+            transaction.commit().await.map_err(DBError::from)?;
+        }
+
+        return Ok(summary);
+    }
+
+    #[instrument(skip(self, records), fields(records = records.len()))]
+    async fn merge(&self, records: Vec<ExportRecord>) -> Result<ImportSummary, DBError> {
+        let _timer = QueryTimer::start("backup", "merge");
+
+        let (questions, answers) = split(records);
+        let questions = QuestionColumns::new(questions);
+        let answers = AnswerColumns::new(answers);
+
+        let mut transaction = self.database.begin().await.map_err(DBError::from)?;
+
+        mark_bulk_write(&mut transaction).await?;
+
+        let stored_questions = sqlx::query!(
+            r#"
+                INSERT INTO questions (id, title, description, description_html, created_at)
+                SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[], $5::timestamptz[])
+                ON CONFLICT (id) DO NOTHING
+            "#,
+            &questions.ids,
+            &questions.titles,
+            &questions.descriptions,
+            &questions.descriptions_html,
+            &questions.created_at
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(DBError::from)?;
+
+        let stored_answers = sqlx::query!(
+            r#"
+                INSERT INTO answers (id, question_id, content, content_html, created_at)
+                SELECT a.* FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::text[], $5::timestamptz[])
+                    AS a(id, question_id, content, content_html, created_at)
+                WHERE EXISTS (SELECT 1 FROM questions q WHERE q.id = a.question_id)
+                ON CONFLICT (id) DO NOTHING
+            "#,
+            &answers.ids,
+            &answers.question_ids,
+            &answers.contents,
+            &answers.contents_html,
+            &answers.created_at
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(DBError::from)?;
+
+        transaction.commit().await.map_err(DBError::from)?;
+
+        return Ok(ImportSummary {
+            questions: stored_questions.rows_affected() as usize,
+            answers: stored_answers.rows_affected() as usize,
+            dry_run: false,
+        });
+    }
+}
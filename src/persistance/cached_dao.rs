@@ -0,0 +1,585 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use lru::LruCache;
+use metrics::counter;
+
+use crate::models::*;
+
+use super::{answers_dao::AnswerDAO, questions_dao::QuestionDAO};
+
+struct Entries<K: Hash + Eq, V> {
+    lru: LruCache<K, (Instant, V)>,
+    /// Bumped by every invalidation, so reads that began before one cannot store what they read.
+    generation: u64,
+}
+
+/// Least recently used entries that expire a fixed time after they were stored.
+struct Lru<K: Hash + Eq, V> {
+    name: &'static str,
+    ttl: Duration,
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K: Hash + Eq, V: Clone> Lru<K, V> {
+    fn new(name: &'static str, capacity: NonZeroUsize, ttl: Duration) -> Self {
+        return Self {
+            name,
+            ttl,
+            entries: Mutex::new(Entries {
+                lru: LruCache::new(capacity),
+                generation: 0,
+            }),
+        };
+    }
+
+    /// The cached value, or else the generation to `put` the value read instead with.
+    fn get(&self, key: &K) -> Result<V, u64> {
+        // Panic if another thread panicked while holding the lock
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some((stored_at, value)) = entries.lru.get(key) {
+            if stored_at.elapsed() < self.ttl {
+                counter!("dao_cache_hits_total", "cache" => self.name).increment(1);
+                return Ok(value.clone());
+            }
+
+            entries.lru.pop(key);
+        }
+
+        counter!("dao_cache_misses_total", "cache" => self.name).increment(1);
+
+        return Err(entries.generation);
+    }
+
+    /// Stores a value read after `get` missed, unless something was invalidated meanwhile, as
+    /// the value may predate that change.
+    fn put(&self, key: K, value: V, generation: u64) {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.generation == generation {
+            entries.lru.put(key, (Instant::now(), value));
+        }
+    }
+
+    fn invalidate(&self, key: &K) {
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.generation += 1;
+        entries.lru.pop(key);
+    }
+
+    fn invalidate_where(&self, stale: impl Fn(&V) -> bool) {
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.generation += 1;
+        entries.lru.retain(|_, (_, value)| !stale(value));
+    }
+
+    fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.generation += 1;
+        entries.lru.clear();
+    }
+}
+
+/// A write that makes cached reads stale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Eviction {
+    QuestionCreated,
+    QuestionChanged(QuestionId),
+    QuestionDeleted(QuestionId),
+    AnswerCreated(QuestionId),
+    AnswerDeleted(AnswerId),
+}
+
+/// Entries shared by the cached question and answer DAOs, so a write through either one
+/// invalidates what the other has read.
+pub struct DaoCache {
+    questions: Lru<(), Vec<Question>>,
+    question: Lru<QuestionId, QuestionDetail>,
+    answers: Lru<QuestionId, Vec<Answer>>,
+    answer: Lru<AnswerId, Answer>,
+}
+
+impl DaoCache {
+    /// Keeps up to `capacity` entries of each kind for at most `ttl`.
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        return Self {
+            questions: Lru::new("questions", NonZeroUsize::MIN, ttl),
+            question: Lru::new("question", capacity, ttl),
+            answers: Lru::new("answers", capacity, ttl),
+            answer: Lru::new("answer", capacity, ttl),
+        };
+    }
+
+    pub fn question_created(&self) {
+        self.questions.invalidate(&());
+    }
+
+    /// Evicts a question whose row changed, such as its view count.
+    pub fn question_changed(&self, question_id: QuestionId) {
+        self.question.invalidate(&question_id);
+    }
+
+    pub fn question_deleted(&self, question_id: QuestionId) {
+        self.questions.invalidate(&());
+        self.answer_created(question_id);
+        self.answer
+            .invalidate_where(|answer| answer.detail.question_uuid == question_id);
+    }
+
+    pub fn answer_created(&self, question_id: QuestionId) {
+        self.question.invalidate(&question_id);
+        self.answers.invalidate(&question_id);
+    }
+
+    pub fn answer_deleted(&self, id: AnswerId) {
+        // Only the answer knows its question, and it may not be cached
+        let contains = |answers: &[Answer]| answers.iter().any(|answer| answer.answer_uuid == id);
+
+        self.answer.invalidate(&id);
+        self.question
+            .invalidate_where(|question| contains(&question.answers));
+        self.answers.invalidate_where(|answers| contains(answers));
+    }
+
+    pub fn evict(&self, eviction: Eviction) {
+        match eviction {
+            Eviction::QuestionCreated => self.question_created(),
+            Eviction::QuestionChanged(question_id) => self.question_changed(question_id),
+            Eviction::QuestionDeleted(question_id) => self.question_deleted(question_id),
+            Eviction::AnswerCreated(question_id) => self.answer_created(question_id),
+            Eviction::AnswerDeleted(id) => self.answer_deleted(id),
+        }
+    }
+
+    /// Evicts everything, for when changes may have been missed.
+    pub fn clear(&self) {
+        self.questions.clear();
+        self.question.clear();
+        self.answers.clear();
+        self.answer.clear();
+    }
+}
+
+/// Caches the reads of any question or answer DAO and invalidates them on writes made through
+/// it. Writes by other instances or that bypass it are evicted by the change listener in
+/// `changes`, and bulk writes such as imports, which set `app.bulk_write`, clear the cache. The
+/// entries only expire as a fallback, for changes missed while the listener is disconnected and
+/// view counts flushed by other instances, which are not notified.
+pub struct CachedDAO<D> {
+    inner: D,
+    cache: Arc<DaoCache>,
+}
+
+impl<D> CachedDAO<D> {
+    pub fn new(inner: D, cache: Arc<DaoCache>) -> Self {
+        return Self { inner, cache };
+    }
+}
+
+#[async_trait]
+impl<D: QuestionDAO + Send + Sync> QuestionDAO for CachedDAO<D> {
+    async fn create_question(&self, question: QuestionFields) -> Result<Question, DBError> {
+        let question = self.inner.create_question(question).await?;
+
+        self.cache.question_created();
+
+        return Ok(question);
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionId) -> Result<(), DBError> {
+        self.inner.delete_question(question_uuid).await?;
+
+        self.cache.question_deleted(question_uuid);
+
+        return Ok(());
+    }
+
+    async fn get_question(&self, question_uuid: QuestionId) -> Result<QuestionDetail, DBError> {
+        let generation = match self.cache.question.get(&question_uuid) {
+            Ok(question) => return Ok(question),
+            Err(generation) => generation,
+        };
+
+        let question = self.inner.get_question(question_uuid).await?;
+
+        self.cache
+            .question
+            .put(question_uuid, question.clone(), generation);
+
+        return Ok(question);
+    }
+
+    async fn lock_question(&self, question_uuid: QuestionId) -> Result<(), DBError> {
+        return self.inner.lock_question(question_uuid).await;
+    }
+
+    async fn get_questions(&self) -> Result<Vec<Question>, DBError> {
+        let generation = match self.cache.questions.get(&()) {
+            Ok(questions) => return Ok(questions),
+            Err(generation) => generation,
+        };
+
+        let questions = self.inner.get_questions().await?;
+
+        self.cache.questions.put((), questions.clone(), generation);
+
+        return Ok(questions);
+    }
+
+    async fn get_latest_questions(&self, limit: i64) -> Result<Vec<Question>, DBError> {
+        return self.inner.get_latest_questions(limit).await;
+    }
+
+    async fn question_exists(&self, question_uuid: QuestionId) -> Result<bool, DBError> {
+        return self.inner.question_exists(question_uuid).await;
+    }
+
+    async fn add_views(&self, views: HashMap<QuestionId, i64>) -> Result<(), DBError> {
+        let questions: Vec<QuestionId> = views.keys().copied().collect();
+
+        self.inner.add_views(views).await?;
+
+        for question_uuid in questions {
+            self.cache.question_changed(question_uuid);
+        }
+
+        return Ok(());
+    }
+}
+
+#[async_trait]
+impl<D: AnswerDAO + Send + Sync> AnswerDAO for CachedDAO<D> {
+    async fn create_answer(&self, details: AnswerFields) -> Result<Answer, DBError> {
+        let answer = self.inner.create_answer(details).await?;
+
+        self.cache.answer_created(answer.detail.question_uuid);
+
+        return Ok(answer);
+    }
+
+    async fn delete_answer(&self, id: AnswerId) -> Result<(), DBError> {
+        self.inner.delete_answer(id).await?;
+
+        self.cache.answer_deleted(id);
+
+        return Ok(());
+    }
+
+    async fn get_answer(&self, id: AnswerId) -> Result<Answer, DBError> {
+        let generation = match self.cache.answer.get(&id) {
+            Ok(answer) => return Ok(answer),
+            Err(generation) => generation,
+        };
+
+        let answer = self.inner.get_answer(id).await?;
+
+        self.cache.answer.put(id, answer.clone(), generation);
+
+        return Ok(answer);
+    }
+
+    async fn lock_answer(&self, id: AnswerId) -> Result<(), DBError> {
+        return self.inner.lock_answer(id).await;
+    }
+
+    async fn get_answers(&self, question_id: QuestionId) -> Result<Vec<Answer>, DBError> {
+        let generation = match self.cache.answers.get(&question_id) {
+            Ok(answers) => return Ok(answers),
+            Err(generation) => generation,
+        };
+
+        let answers = self.inner.get_answers(question_id).await?;
+
+        self.cache
+            .answers
+            .put(question_id, answers.clone(), generation);
+
+        return Ok(answers);
+    }
+}
+
+/// Passes the reads of a question or answer DAO in a unit of work around the cache, and records
+/// its writes to evict once the work is committed. Evicting any earlier would let concurrent
+/// reads cache what the work is about to change.
+pub struct DeferredDAO<D> {
+    inner: D,
+    evictions: Arc<Mutex<Vec<Eviction>>>,
+}
+
+impl<D> DeferredDAO<D> {
+    pub fn new(inner: D, evictions: Arc<Mutex<Vec<Eviction>>>) -> Self {
+        return Self { inner, evictions };
+    }
+
+    fn record(&self, eviction: Eviction) {
+        // Panic if another thread panicked while holding the lock
+        self.evictions.lock().unwrap().push(eviction);
+    }
+}
+
+#[async_trait]
+impl<D: QuestionDAO + Send + Sync> QuestionDAO for DeferredDAO<D> {
+    async fn create_question(&self, question: QuestionFields) -> Result<Question, DBError> {
+        let question = self.inner.create_question(question).await?;
+
+        self.record(Eviction::QuestionCreated);
+
+        return Ok(question);
+    }
+
+    async fn delete_question(&self, question_uuid: QuestionId) -> Result<(), DBError> {
+        self.inner.delete_question(question_uuid).await?;
+
+        self.record(Eviction::QuestionDeleted(question_uuid));
+
+        return Ok(());
+    }
+
+    async fn get_question(&self, question_uuid: QuestionId) -> Result<QuestionDetail, DBError> {
+        return self.inner.get_question(question_uuid).await;
+    }
+
+    async fn lock_question(&self, question_uuid: QuestionId) -> Result<(), DBError> {
+        return self.inner.lock_question(question_uuid).await;
+    }
+
+    async fn get_questions(&self) -> Result<Vec<Question>, DBError> {
+        return self.inner.get_questions().await;
+    }
+
+    async fn get_latest_questions(&self, limit: i64) -> Result<Vec<Question>, DBError> {
+        return self.inner.get_latest_questions(limit).await;
+    }
+
+    async fn question_exists(&self, question_uuid: QuestionId) -> Result<bool, DBError> {
+        return self.inner.question_exists(question_uuid).await;
+    }
+
+    async fn add_views(&self, views: HashMap<QuestionId, i64>) -> Result<(), DBError> {
+        let questions: Vec<QuestionId> = views.keys().copied().collect();
+
+        self.inner.add_views(views).await?;
+
+        for question_uuid in questions {
+            self.record(Eviction::QuestionChanged(question_uuid));
+        }
+
+        return Ok(());
+    }
+}
+
+#[async_trait]
+impl<D: AnswerDAO + Send + Sync> AnswerDAO for DeferredDAO<D> {
+    async fn create_answer(&self, details: AnswerFields) -> Result<Answer, DBError> {
+        let answer = self.inner.create_answer(details).await?;
+
+        self.record(Eviction::AnswerCreated(answer.detail.question_uuid));
+
+        return Ok(answer);
+    }
+
+    async fn delete_answer(&self, id: AnswerId) -> Result<(), DBError> {
+        self.inner.delete_answer(id).await?;
+
+        self.record(Eviction::AnswerDeleted(id));
+
+        return Ok(());
+    }
+
+    async fn get_answer(&self, id: AnswerId) -> Result<Answer, DBError> {
+        return self.inner.get_answer(id).await;
+    }
+
+    async fn lock_answer(&self, id: AnswerId) -> Result<(), DBError> {
+        return self.inner.lock_answer(id).await;
+    }
+
+    async fn get_answers(&self, question_id: QuestionId) -> Result<Vec<Answer>, DBError> {
+        return self.inner.get_answers(question_id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::persistance::{answers_dao, questions_dao};
+
+    const CAPACITY: NonZeroUsize = NonZeroUsize::new(2).unwrap();
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn lru_should_evict_least_recently_used() {
+        let lru = Lru::new("test", CAPACITY, TTL);
+
+        for key in 1..=2 {
+            let generation = lru.get(&key).unwrap_err();
+            lru.put(key, key * 10, generation);
+        }
+
+        assert_eq!(lru.get(&1), Ok(10));
+
+        let generation = lru.get(&3).unwrap_err();
+        lru.put(3, 30, generation);
+
+        assert_eq!(lru.get(&1), Ok(10));
+        assert!(lru.get(&2).is_err());
+        assert_eq!(lru.get(&3), Ok(30));
+    }
+
+    #[test]
+    fn lru_should_expire_entries() {
+        let lru = Lru::new("test", CAPACITY, Duration::ZERO);
+
+        let generation = lru.get(&1).unwrap_err();
+        lru.put(1, 10, generation);
+
+        assert!(lru.get(&1).is_err());
+    }
+
+    #[test]
+    fn lru_should_not_store_reads_older_than_an_invalidation() {
+        let lru = Lru::new("test", CAPACITY, TTL);
+
+        let generation = lru.get(&1).unwrap_err();
+        lru.invalidate(&2);
+        lru.put(1, 10, generation);
+
+        assert!(lru.get(&1).is_err());
+    }
+
+    fn cached(pool: &PgPool) -> (CachedDAO<questions_dao::DAO>, CachedDAO<answers_dao::DAO>) {
+        let cache = Arc::new(DaoCache::new(CAPACITY, TTL));
+
+        return (
+            CachedDAO::new(questions_dao::DAO::new(pool.clone()), cache.clone()),
+            CachedDAO::new(answers_dao::DAO::new(pool.clone()), cache),
+        );
+    }
+
+    async fn delete_behind_the_cache(pool: &PgPool) -> Result<(), String> {
+        sqlx::query("DELETE FROM questions")
+            .execute(pool)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn reads_should_be_served_from_the_cache(pool: PgPool) -> Result<(), String> {
+        let (questions, answers) = cached(&pool);
+
+        let question = questions
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        let answer = answers
+            .create_answer(AnswerFields {
+                question_uuid: question.question_uuid,
+                content: "test content".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let detail = questions.get_question(question.question_uuid).await.ok();
+        let listed = questions.get_questions().await.ok();
+        let answered = answers.get_answers(question.question_uuid).await.ok();
+        let read = answers.get_answer(answer.answer_uuid).await.ok();
+
+        delete_behind_the_cache(&pool).await?;
+
+        assert!(detail.is_some() && listed.is_some() && answered.is_some() && read.is_some());
+        assert_eq!(
+            questions.get_question(question.question_uuid).await.ok(),
+            detail
+        );
+        assert_eq!(questions.get_questions().await.ok(), listed);
+        assert_eq!(
+            answers.get_answers(question.question_uuid).await.ok(),
+            answered
+        );
+        assert_eq!(answers.get_answer(answer.answer_uuid).await.ok(), read);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn writes_should_invalidate_across_daos(pool: PgPool) -> Result<(), String> {
+        let (questions, answers) = cached(&pool);
+
+        let question = questions
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        questions
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let answer = answers
+            .create_answer(AnswerFields {
+                question_uuid: question.question_uuid,
+                content: "test content".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let detail = questions
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if detail.answers != vec![answer.clone()] {
+            return Err("Creating an answer did not refresh its question.".to_owned());
+        }
+
+        answers
+            .delete_answer(answer.answer_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let detail = questions
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if !detail.answers.is_empty() {
+            return Err("Deleting an answer did not refresh its question.".to_owned());
+        }
+
+        questions
+            .delete_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let result = questions.get_question(question.question_uuid).await;
+
+        if let Err(DBError::NotFound(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a not found error but got the following result: {:?}",
+                result
+            ))
+        }
+    }
+}
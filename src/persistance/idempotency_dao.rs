@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::sync::Mutex;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{models::*, telemetry::QueryTimer};
+
+use super::unit_of_work::Database;
+
+/// How long a key is remembered after its first use.
+const KEY_TTL_SECONDS: f64 = 24.0 * 60.0 * 60.0;
+
+/// Outcome of claiming an idempotency key.
+#[derive(Debug, PartialEq)]
+pub enum Claim {
+    /// The key is new or expired, and the request should run. Its response is stored with the
+    /// token of this claim.
+    Claimed(Uuid),
+    /// An earlier request with the same fingerprint succeeded with this response.
+    Completed(serde_json::Value),
+    /// The key was used for a different request.
+    Mismatch,
+}
+
+/// Keys are claimed and completed in the unit of work of the request they belong to, so a key is
+/// only ever seen with the response of a committed request. Claiming a key that another unit of
+/// work holds waits until that work is committed or rolled back.
+#[async_trait]
+pub trait IdempotencyDAO {
+    /// Claims `key` for the request with `fingerprint`, unless an unexpired key holds it.
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<Claim, DBError>;
+    /// Stores the JSON response to replay for the key, if it is still held by the claim `token`.
+    async fn complete(&self, key: &str, token: Uuid, response: &str) -> Result<(), DBError>;
+    /// Deletes expired keys, returning how many there were.
+    async fn delete_expired(&self) -> Result<u64, DBError>;
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct DAO {
+    database: Database,
+}
+
+impl DAO {
+    pub fn new(database: PgPool) -> Self {
+        return Self {
+            database: Database::Pool(database),
+        };
+    }
+
+    pub fn in_transaction(transaction: Arc<Mutex<Transaction<'static, Postgres>>>) -> Self {
+        return Self {
+            database: Database::Transaction(transaction),
+        };
+    }
+}
+
+#[async_trait]
+impl IdempotencyDAO for DAO {
+    #[instrument(skip(self))]
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<Claim, DBError> {
+        let _timer = QueryTimer::start("idempotency_keys", "claim");
+
+        // Expired keys are taken over as if they were new
+        let token = sqlx::query_scalar!(
+            r#"
+                INSERT INTO idempotency_keys (key, token, fingerprint, expires_at)
+                VALUES ($1, $2, $3, CURRENT_TIMESTAMP + make_interval(secs => $4))
+                ON CONFLICT (key) DO UPDATE
+                SET token = EXCLUDED.token,
+                    fingerprint = EXCLUDED.fingerprint,
+                    response = NULL,
+                    created_at = CURRENT_TIMESTAMP,
+                    expires_at = EXCLUDED.expires_at
+                WHERE idempotency_keys.expires_at <= CURRENT_TIMESTAMP
+                RETURNING token
+            "#,
+            key,
+            Uuid::new_v4(),
+            fingerprint,
+            KEY_TTL_SECONDS
+        )
+        .fetch_optional(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        if let Some(token) = token {
+            return Ok(Claim::Claimed(token));
+        }
+
+        let existing = sqlx::query!(
+            "SELECT fingerprint, response FROM idempotency_keys WHERE key = $1",
+            key
+        )
+        .fetch_optional(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return match existing {
+            Some(existing) if existing.fingerprint != fingerprint => Ok(Claim::Mismatch),
+            Some(existing) => existing.response.map(Claim::Completed).ok_or_else(|| {
+                // Other units of work only see keys once they are completed
+                return DBError::UniqueViolation(format!(
+                    "Idempotency key already claimed: {}",
+                    key
+                ));
+            }),
+            // Purged since it expired, so the key is free again
+            None => self.claim(key, fingerprint).await,
+        };
+    }
+
+    #[instrument(skip(self, response))]
+    async fn complete(&self, key: &str, token: Uuid, response: &str) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("idempotency_keys", "complete");
+
+        let result = sqlx::query!(
+            r#"
+                UPDATE idempotency_keys SET response = $3::TEXT::JSONB
+                WHERE key = $1 AND token = $2
+            "#,
+            key,
+            token,
+            response
+        )
+        .execute(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        if result.rows_affected() == 0 {
+            // The claim was taken over, so the response must not be stored as its own
+            return Err(DBError::Other(
+                format!("Idempotency key {} is no longer claimed by {}", key, token).into(),
+            ));
+        }
+
+        return Ok(());
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_expired(&self) -> Result<u64, DBError> {
+        let _timer = QueryTimer::start("idempotency_keys", "delete_expired");
+
+        let result =
+            sqlx::query!("DELETE FROM idempotency_keys WHERE expires_at <= CURRENT_TIMESTAMP")
+                .execute(&mut *self.database.acquire().await?)
+                .await
+                .map_err(DBError::from)?;
+
+        return Ok(result.rows_affected());
+    }
+}
@@ -1,5 +1,39 @@
+use sqlx::error::ErrorKind;
+
+use crate::models::DBError;
+
 pub mod answers_dao;
+pub mod backup_dao;
+pub mod cached_dao;
+pub mod idempotency_dao;
 pub mod questions_dao;
+mod rows;
+pub mod unit_of_work;
+pub mod webhooks_dao;
 
 #[cfg(test)]
 mod tests;
+
+/// SQLSTATE raised when a serializable transaction cannot be committed.
+const SERIALIZATION_FAILURE: &str = "40001";
+
+impl From<sqlx::Error> for DBError {
+    fn from(error: sqlx::Error) -> Self {
+        if let Some(db_error) = error.as_database_error() {
+            let message = db_error.message().to_owned();
+
+            match db_error.kind() {
+                ErrorKind::ForeignKeyViolation => return Self::ForeignKeyViolation(message),
+                ErrorKind::UniqueViolation => return Self::UniqueViolation(message),
+                ErrorKind::CheckViolation => return Self::CheckViolation(message),
+                _ => {}
+            }
+
+            if db_error.code().as_deref() == Some(SERIALIZATION_FAILURE) {
+                return Self::SerializationFailure(message);
+            }
+        }
+
+        return Self::Other(Box::new(error));
+    }
+}
@@ -1,79 +1,275 @@
+use std::{collections::HashMap, sync::Arc};
+
 use async_trait::async_trait;
-use sqlx::PgPool;
-use uuid::Uuid;
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::{markdown, models::*, telemetry::QueryTimer};
 
-use crate::models::*;
+use super::{
+    rows::{AnswerRow, QuestionRow},
+    unit_of_work::Database,
+};
 
 #[async_trait]
 pub trait QuestionDAO {
     async fn create_question(&self, question: QuestionFields) -> Result<Question, DBError>;
-    async fn delete_question(&self, question_uuid: Uuid) -> Result<(), DBError>;
+    async fn delete_question(&self, question_uuid: QuestionId) -> Result<(), DBError>;
+    async fn get_question(&self, question_uuid: QuestionId) -> Result<QuestionDetail, DBError>;
+    /// Locks the question and its answers against changes by others until the unit of work it
+    /// runs in ends.
+    async fn lock_question(&self, question_uuid: QuestionId) -> Result<(), DBError>;
+    /// Every question, oldest first.
     async fn get_questions(&self) -> Result<Vec<Question>, DBError>;
+    /// The `limit` most recently created questions, newest first.
+    async fn get_latest_questions(&self, limit: i64) -> Result<Vec<Question>, DBError>;
+    /// Whether the question exists, without reading it or its answers.
+    async fn question_exists(&self, question_uuid: QuestionId) -> Result<bool, DBError>;
+    /// Adds the given number of views to each question, ignoring questions that no longer exist.
+    async fn add_views(&self, views: HashMap<QuestionId, i64>) -> Result<(), DBError>;
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct DAO {
-    database: PgPool,
+    database: Database,
 }
 
 impl DAO {
     pub fn new(database: PgPool) -> Self {
-        return Self { database };
+        return Self {
+            database: Database::Pool(database),
+        };
+    }
+
+    pub fn in_transaction(transaction: Arc<Mutex<Transaction<'static, Postgres>>>) -> Self {
+        return Self {
+            database: Database::Transaction(transaction),
+        };
     }
 }
 
 #[async_trait]
 impl QuestionDAO for DAO {
+    #[instrument(skip(self, question))]
     async fn create_question(&self, question: QuestionFields) -> Result<Question, DBError> {
+        let description_html = markdown::render(&question.description);
+
+        let _timer = QueryTimer::start("questions", "create_question");
+
         let record = sqlx::query!(
             r#"
-                INSERT INTO questions (title, description)
-                VALUES ($1, $2)
+                INSERT INTO questions (title, description, description_html)
+                VALUES ($1, $2, $3)
                 RETURNING *
             "#,
             question.title,
-            question.description
+            question.description,
+            description_html
         )
-        .fetch_one(&self.database)
+        .fetch_one(&mut *self.database.acquire().await?)
         .await
-        .map_err(|e| DBError::Other(Box::new(e)))?;
+        .map_err(DBError::from)?;
 
         return Ok(Question {
-            question_uuid: record.id,
+            question_uuid: QuestionId(record.id),
             detail: QuestionFields {
                 title: record.title,
                 description: record.description,
             },
+            description_html,
             created_at: record.created_at,
         });
     }
 
-    async fn delete_question(&self, id: Uuid) -> Result<(), DBError> {
-        if id.is_nil() {
-            return Err(DBError::InvalidUUID(format!("Invalid question id: {}", id)));
+    #[instrument(skip(self))]
+    async fn delete_question(&self, id: QuestionId) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("questions", "delete_question");
+
+        let result = sqlx::query!("DELETE FROM questions WHERE id = $1", id.0)
+            .execute(&mut *self.database.acquire().await?)
+            .await
+            .map_err(DBError::from)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DBError::NotFound(format!("No question with id: {}", id)));
         }
 
-        sqlx::query!("DELETE FROM questions WHERE id = $1", id)
-            .execute(&self.database)
+        return Ok(());
+    }
+
+    #[instrument(skip(self))]
+    async fn get_question(&self, id: QuestionId) -> Result<QuestionDetail, DBError> {
+        let _timer = QueryTimer::start("questions", "get_question");
+
+        // A single join fetches the question and its answers; a question without answers
+        // yields one row whose answer columns are all NULL
+        let records = sqlx::query!(
+            r#"
+                SELECT
+                    q.id, q.title, q.description, q.description_html, q.view_count, q.created_at,
+                    a.id AS "answer_id?",
+                    a.content AS "answer_content?",
+                    a.content_html AS answer_content_html,
+                    a.created_at AS "answer_created_at?"
+                FROM questions q
+                LEFT JOIN answers a ON a.question_id = q.id
+                WHERE q.id = $1
+                ORDER BY a.created_at, a.id
+            "#,
+            id.0
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        let Some(first) = records.first() else {
+            return Err(DBError::NotFound(format!("No question with id: {}", id)));
+        };
+
+        let question = Question::from(QuestionRow {
+            id: first.id,
+            title: first.title.clone(),
+            description: first.description.clone(),
+            description_html: first.description_html.clone(),
+            created_at: first.created_at,
+        });
+        let view_count = first.view_count;
+
+        let answers: Vec<Answer> = records
+            .into_iter()
+            .filter_map(|record| {
+                // Without answers the join yields a single row with no answer columns
+                let (Some(id), Some(content), Some(created_at)) = (
+                    record.answer_id,
+                    record.answer_content,
+                    record.answer_created_at,
+                ) else {
+                    return None;
+                };
+
+                return Some(Answer::from(AnswerRow {
+                    id,
+                    question_id: question.question_uuid.0,
+                    content,
+                    content_html: record.answer_content_html,
+                    created_at,
+                }));
+            })
+            .collect();
+
+        return Ok(QuestionDetail {
+            view_count,
+            question,
+            answer_count: answers.len(),
+            answers,
+        });
+    }
+
+    #[instrument(skip(self))]
+    async fn lock_question(&self, id: QuestionId) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("questions", "lock_question");
+
+        let mut connection = self.database.acquire().await?;
+
+        // Locking the question also holds off new answers, whose foreign key must share it
+        let locked = sqlx::query!("SELECT id FROM questions WHERE id = $1 FOR UPDATE", id.0)
+            .fetch_optional(&mut *connection)
             .await
-            .map_err(|e| DBError::Other(Box::new(e)))?;
+            .map_err(DBError::from)?;
+
+        if locked.is_none() {
+            return Err(DBError::NotFound(format!("No question with id: {}", id)));
+        }
+
+        sqlx::query!(
+            "SELECT id FROM answers WHERE question_id = $1 FOR UPDATE",
+            id.0
+        )
+        .fetch_all(&mut *connection)
+        .await
+        .map_err(DBError::from)?;
 
         return Ok(());
     }
 
+    #[instrument(skip(self))]
     async fn get_questions(&self) -> Result<Vec<Question>, DBError> {
-        return Ok(sqlx::query!("SELECT * FROM questions")
-            .fetch_all(&self.database)
-            .await
-            .map_err(|e| DBError::Other(Box::new(e)))?
+        let _timer = QueryTimer::start("questions", "get_questions");
+
+        return Ok(sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT id, title, description, description_html, created_at FROM questions
+                ORDER BY created_at, id
+            "#
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?
+        .into_iter()
+        .map(Question::from)
+        .collect());
+    }
+
+    #[instrument(skip(self))]
+    async fn get_latest_questions(&self, limit: i64) -> Result<Vec<Question>, DBError> {
+        let _timer = QueryTimer::start("questions", "get_latest_questions");
+
+        return Ok(sqlx::query_as!(
+            QuestionRow,
+            r#"
+                SELECT id, title, description, description_html, created_at FROM questions
+                ORDER BY created_at DESC LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?
+        .into_iter()
+        .map(Question::from)
+        .collect());
+    }
+
+    #[instrument(skip(self))]
+    async fn question_exists(&self, id: QuestionId) -> Result<bool, DBError> {
+        let _timer = QueryTimer::start("questions", "question_exists");
+
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM questions WHERE id = $1) AS "exists!""#,
+            id.0
+        )
+        .fetch_one(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(exists);
+    }
+
+    #[instrument(skip_all, fields(questions = views.len()))]
+    async fn add_views(&self, views: HashMap<QuestionId, i64>) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("questions", "add_views");
+
+        let (ids, counts): (Vec<_>, Vec<_>) = views
             .into_iter()
-            .map(|record| Question {
-                question_uuid: record.id,
-                detail: QuestionFields {
-                    title: record.title,
-                    description: record.description,
-                },
-                created_at: record.created_at,
-            })
-            .collect());
+            .map(|(question_id, count)| (question_id.0, count))
+            .unzip();
+
+        sqlx::query!(
+            r#"
+                UPDATE questions q
+                SET view_count = q.view_count + v.views
+                FROM UNNEST($1::uuid[], $2::int8[]) AS v(id, views)
+                WHERE q.id = v.id
+            "#,
+            &ids,
+            &counts
+        )
+        .execute(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(());
     }
 }
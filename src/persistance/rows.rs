@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{markdown, models::*};
+
+/// The columns of a `questions` row that make up a `Question`.
+pub struct QuestionRow {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub description_html: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The columns of an `answers` row that make up an `Answer`.
+pub struct AnswerRow {
+    pub id: Uuid,
+    pub question_id: Uuid,
+    pub content: String,
+    pub content_html: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Rows created before markdown rendering existed have no stored HTML, so it is rendered on read
+
+impl From<QuestionRow> for Question {
+    fn from(row: QuestionRow) -> Self {
+        return Self {
+            question_uuid: QuestionId(row.id),
+            description_html: row
+                .description_html
+                .unwrap_or_else(|| markdown::render(&row.description)),
+            detail: QuestionFields {
+                title: row.title,
+                description: row.description,
+            },
+            created_at: row.created_at,
+        };
+    }
+}
+
+impl From<AnswerRow> for Answer {
+    fn from(row: AnswerRow) -> Self {
+        return Self {
+            answer_uuid: AnswerId(row.id),
+            content_html: row
+                .content_html
+                .unwrap_or_else(|| markdown::render(&row.content)),
+            detail: AnswerFields {
+                question_uuid: QuestionId(row.question_id),
+                content: row.content,
+            },
+            created_at: row.created_at,
+        };
+    }
+}
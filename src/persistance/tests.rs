@@ -3,7 +3,8 @@ mod answers_tests {
     use uuid::Uuid;
 
     use crate::{
-        models::{AnswerFields, DBError, QuestionFields},
+        handlers::conditional::{etag, to_json},
+        models::{AnswerFields, AnswerId, DBError, QuestionFields, QuestionId},
         persistance::{
             answers_dao::{AnswerDAO, DAO as AnswersDaoImpl},
             questions_dao::{QuestionDAO, DAO as QuestionsDaoImpl},
@@ -11,12 +12,12 @@ mod answers_tests {
     };
 
     #[sqlx::test]
-    async fn create_answer_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
+    async fn create_answer_should_fail_with_nil_uuid(pool: PgPool) -> Result<(), String> {
         let answer_doa = AnswersDaoImpl::new(pool);
 
         let result = answer_doa
             .create_answer(AnswerFields {
-                question_uuid: uuid::Uuid::nil(),
+                question_uuid: QuestionId(Uuid::nil()),
                 content: "test content".to_owned(),
             })
             .await;
@@ -28,11 +29,11 @@ mod answers_tests {
             ));
         }
 
-        if let Err(DBError::InvalidUUID(_)) = result {
+        if let Err(DBError::ForeignKeyViolation(_)) = result {
             Ok(())
         } else {
             Err(format!(
-                "Expected an invalid UUID error but got the following error: {:?}",
+                "Expected a foreign key violation but got the following error: {:?}",
                 result.err()
             ))
         }
@@ -44,7 +45,7 @@ mod answers_tests {
 
         let result = answer_doa
             .create_answer(AnswerFields {
-                question_uuid: Uuid::nil(),
+                question_uuid: QuestionId(Uuid::new_v4()),
                 content: "test content".to_owned(),
             })
             .await;
@@ -56,11 +57,11 @@ mod answers_tests {
             ));
         }
 
-        if let Err(DBError::InvalidUUID(_)) = result {
+        if let Err(DBError::ForeignKeyViolation(_)) = result {
             Ok(())
         } else {
             Err(format!(
-                "Expected an invalid UUID error but got the following error: {:?}",
+                "Expected a foreign key violation but got the following error: {:?}",
                 result.err()
             ))
         }
@@ -76,7 +77,7 @@ mod answers_tests {
 
         let result = answer_doa
             .create_answer(AnswerFields {
-                question_uuid: Uuid::new_v4(),
+                question_uuid: QuestionId(Uuid::new_v4()),
                 content: "test content".to_owned(),
             })
             .await;
@@ -99,6 +100,7 @@ mod answers_tests {
     }
 
     #[sqlx::test]
+    #[allow(clippy::cmp_owned)]
     async fn create_answer_should_succeed(pool: PgPool) -> Result<(), String> {
         let question_doa = QuestionsDaoImpl::new(pool.clone());
         let answer_doa = AnswersDaoImpl::new(pool);
@@ -127,10 +129,10 @@ mod answers_tests {
     }
 
     #[sqlx::test]
-    async fn delete_answer_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
+    async fn delete_answer_should_fail_with_nil_uuid(pool: PgPool) -> Result<(), String> {
         let answer_doa = AnswersDaoImpl::new(pool);
 
-        let result = answer_doa.delete_answer(Uuid::nil()).await;
+        let result = answer_doa.delete_answer(AnswerId(Uuid::nil())).await;
 
         if result.is_ok() {
             return Err(format!(
@@ -139,11 +141,11 @@ mod answers_tests {
             ));
         }
 
-        if let Err(DBError::InvalidUUID(_)) = result {
+        if let Err(DBError::NotFound(_)) = result {
             Ok(())
         } else {
             Err(format!(
-                "Expected an invalid UUID error but got the following error: {:?}",
+                "Expected a not found error but got the following error: {:?}",
                 result.err()
             ))
         }
@@ -157,7 +159,7 @@ mod answers_tests {
 
         pool.close().await;
 
-        let result = answer_doa.delete_answer(Uuid::new_v4()).await;
+        let result = answer_doa.delete_answer(AnswerId(Uuid::new_v4())).await;
 
         if result.is_ok() {
             return Err(format!(
@@ -177,6 +179,30 @@ mod answers_tests {
     }
 
     #[sqlx::test]
+    async fn delete_answer_should_fail_with_non_existent_uuid(pool: PgPool) -> Result<(), String> {
+        let answer_doa = AnswersDaoImpl::new(pool);
+
+        let result = answer_doa.delete_answer(AnswerId(Uuid::new_v4())).await;
+
+        if result.is_ok() {
+            return Err(format!(
+                "Expected an error but got the following result: {:?}",
+                result.unwrap()
+            ));
+        }
+
+        if let Err(DBError::NotFound(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a not found error but got the following error: {:?}",
+                result.err()
+            ))
+        }
+    }
+
+    #[sqlx::test]
+    #[allow(clippy::clone_on_copy, clippy::len_zero)]
     async fn delete_answer_should_succeed(pool: PgPool) -> Result<(), String> {
         let question_doa = QuestionsDaoImpl::new(pool.clone());
         let answer_doa = AnswersDaoImpl::new(pool);
@@ -215,10 +241,10 @@ mod answers_tests {
     }
 
     #[sqlx::test]
-    async fn get_answers_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
+    async fn get_answers_should_fail_with_nil_uuid(pool: PgPool) -> Result<(), String> {
         let answer_doa = AnswersDaoImpl::new(pool);
 
-        let result = answer_doa.get_answers(Uuid::nil()).await;
+        let result = answer_doa.get_answers(QuestionId(Uuid::nil())).await;
 
         if result.is_ok() {
             return Err(format!(
@@ -227,11 +253,11 @@ mod answers_tests {
             ));
         }
 
-        if let Err(DBError::InvalidUUID(_)) = result {
+        if let Err(DBError::NotFound(_)) = result {
             Ok(())
         } else {
             Err(format!(
-                "Expected an invalid UUID error but got the following error: {:?}",
+                "Expected a not found error but got the following error: {:?}",
                 result.err()
             ))
         }
@@ -243,7 +269,7 @@ mod answers_tests {
 
         pool.close().await;
 
-        let result = answer_doa.get_answers(Uuid::new_v4()).await;
+        let result = answer_doa.get_answers(QuestionId(Uuid::new_v4())).await;
 
         if result.is_ok() {
             return Err(format!(
@@ -263,6 +289,32 @@ mod answers_tests {
     }
 
     #[sqlx::test]
+    async fn get_answers_should_fail_with_non_existent_question(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let answer_doa = AnswersDaoImpl::new(pool);
+
+        let result = answer_doa.get_answers(QuestionId(Uuid::new_v4())).await;
+
+        if result.is_ok() {
+            return Err(format!(
+                "Expected an error but got the following result: {:?}",
+                result.unwrap()
+            ));
+        }
+
+        if let Err(DBError::NotFound(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a not found error but got the following error: {:?}",
+                result.err()
+            ))
+        }
+    }
+
+    #[sqlx::test]
+    #[allow(clippy::clone_on_copy, clippy::get_first)]
     async fn get_answers_should_succeed(pool: PgPool) -> Result<(), String> {
         let question_doa = QuestionsDaoImpl::new(pool.clone());
         let answer_doa = AnswersDaoImpl::new(pool);
@@ -298,15 +350,123 @@ mod answers_tests {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn get_answers_should_keep_their_etag_across_updates(pool: PgPool) -> Result<(), String> {
+        let question_doa = QuestionsDaoImpl::new(pool.clone());
+        let answer_doa = AnswersDaoImpl::new(pool.clone());
+
+        let question = question_doa
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let mut created = Vec::new();
+
+        for _ in 0..5 {
+            let answer = answer_doa
+                .create_answer(AnswerFields {
+                    question_uuid: question.question_uuid,
+                    content: "test content".to_owned(),
+                })
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+
+            created.push(answer.answer_uuid.0);
+        }
+
+        let before = answer_doa
+            .get_answers(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        // Updated rows move within the heap, which would reorder an unordered scan
+        sqlx::query!(
+            "UPDATE answers SET content = content WHERE id = ANY($1)",
+            &[created[0], created[2]]
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+
+        let after = answer_doa
+            .get_answers(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if etag(to_json(&after).as_bytes()) != etag(to_json(&before).as_bytes()) {
+            return Err(format!("The answers were reordered: {:?}", after));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_answer_should_fail_with_non_existent_uuid(pool: PgPool) -> Result<(), String> {
+        let answer_doa = AnswersDaoImpl::new(pool);
+
+        let result = answer_doa.get_answer(AnswerId(Uuid::new_v4())).await;
+
+        if let Err(DBError::NotFound(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a not found error but got the following result: {:?}",
+                result
+            ))
+        }
+    }
+
+    #[sqlx::test]
+    async fn get_answer_should_succeed(pool: PgPool) -> Result<(), String> {
+        let question_doa = QuestionsDaoImpl::new(pool.clone());
+        let answer_doa = AnswersDaoImpl::new(pool);
+
+        let question = question_doa
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let answer = answer_doa
+            .create_answer(AnswerFields {
+                question_uuid: question.question_uuid,
+                content: "test content".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let result = answer_doa
+            .get_answer(answer.answer_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if result != answer {
+            return Err("Incorrect answer returned.".to_owned());
+        }
+
+        Ok(())
+    }
 }
 
 mod questions_tests {
+    use std::collections::HashMap;
+
     use sqlx::PgPool;
     use uuid::Uuid;
 
     use crate::{
-        models::{DBError, QuestionFields},
-        persistance::questions_dao::{QuestionDAO, DAO as QuestionsDaoImpl},
+        handlers::conditional::{etag, to_json},
+        models::{AnswerFields, AnswerId, DBError, QuestionFields, QuestionId},
+        persistance::{
+            answers_dao::{AnswerDAO, DAO as AnswersDaoImpl},
+            questions_dao::{QuestionDAO, DAO as QuestionsDaoImpl},
+        },
     };
 
     #[sqlx::test]
@@ -342,6 +502,7 @@ mod questions_tests {
     }
 
     #[sqlx::test]
+    #[allow(clippy::cmp_owned)]
     async fn create_question_should_succeed(pool: PgPool) -> Result<(), String> {
         let doa = QuestionsDaoImpl::new(pool);
 
@@ -363,10 +524,35 @@ mod questions_tests {
     }
 
     #[sqlx::test]
-    async fn delete_question_should_fail_with_malformed_uuid(pool: PgPool) -> Result<(), String> {
+    async fn create_question_should_store_rendered_markdown(pool: PgPool) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let result = doa
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "**bold** <script>alert(1)</script>".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let results = doa.get_questions().await.map_err(|e| format!("{:?}", e))?;
+
+        if result.description_html != "<p><strong>bold</strong> </p>\n" {
+            return Err(format!("Unexpected HTML: {}", result.description_html));
+        }
+
+        if results.first().unwrap().description_html != result.description_html {
+            return Err("Stored HTML does not match the created question".to_owned());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn delete_question_should_fail_with_nil_uuid(pool: PgPool) -> Result<(), String> {
         let doa = QuestionsDaoImpl::new(pool);
 
-        let result = doa.delete_question(Uuid::nil()).await;
+        let result = doa.delete_question(QuestionId(Uuid::nil())).await;
 
         if result.is_ok() {
             return Err(format!(
@@ -375,11 +561,11 @@ mod questions_tests {
             ));
         }
 
-        if let Err(DBError::InvalidUUID(_)) = result {
+        if let Err(DBError::NotFound(_)) = result {
             Ok(())
         } else {
             Err(format!(
-                "Expected an invalid UUID error but got the following error: {:?}",
+                "Expected a not found error but got the following error: {:?}",
                 result.err()
             ))
         }
@@ -393,7 +579,7 @@ mod questions_tests {
 
         pool.close().await;
 
-        let result = doa.delete_question(Uuid::new_v4()).await;
+        let result = doa.delete_question(QuestionId(Uuid::new_v4())).await;
 
         if result.is_ok() {
             return Err(format!(
@@ -413,6 +599,32 @@ mod questions_tests {
     }
 
     #[sqlx::test]
+    async fn delete_question_should_fail_with_non_existent_uuid(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let result = doa.delete_question(QuestionId(Uuid::new_v4())).await;
+
+        if result.is_ok() {
+            return Err(format!(
+                "Expected an error but got the following result: {:?}",
+                result.unwrap()
+            ));
+        }
+
+        if let Err(DBError::NotFound(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a not found error but got the following error: {:?}",
+                result.err()
+            ))
+        }
+    }
+
+    #[sqlx::test]
+    #[allow(clippy::len_zero)]
     async fn delete_question_should_succeed(pool: PgPool) -> Result<(), String> {
         let doa = QuestionsDaoImpl::new(pool);
 
@@ -437,6 +649,123 @@ mod questions_tests {
         Ok(())
     }
 
+    #[sqlx::test]
+    async fn get_question_should_fail_with_non_existent_uuid(pool: PgPool) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let result = doa.get_question(QuestionId(Uuid::new_v4())).await;
+
+        if let Err(DBError::NotFound(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a not found error but got the following result: {:?}",
+                result
+            ))
+        }
+    }
+
+    #[sqlx::test]
+    async fn question_exists_should_only_find_stored_questions(pool: PgPool) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let question = doa
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let stored = doa
+            .question_exists(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        let missing = doa
+            .question_exists(QuestionId(Uuid::new_v4()))
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if !stored || missing {
+            return Err(format!("Incorrect existence: {} and {}", stored, missing));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_question_should_succeed_without_answers(pool: PgPool) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let question = doa
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let result = doa
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if result.question != question {
+            return Err("Incorrect question returned.".to_owned());
+        }
+
+        if result.answer_count != 0 || !result.answers.is_empty() {
+            return Err("Expected no answers.".to_owned());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_question_should_embed_answers_oldest_first(pool: PgPool) -> Result<(), String> {
+        let question_doa = QuestionsDaoImpl::new(pool.clone());
+        let answer_doa = AnswersDaoImpl::new(pool);
+
+        let question = question_doa
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let mut expected = Vec::new();
+
+        for content in ["first answer", "second answer"] {
+            let answer = answer_doa
+                .create_answer(AnswerFields {
+                    question_uuid: question.question_uuid,
+                    content: content.to_owned(),
+                })
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+
+            expected.push(answer.answer_uuid);
+        }
+
+        let result = question_doa
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let returned: Vec<AnswerId> = result
+            .answers
+            .iter()
+            .map(|answer| answer.answer_uuid)
+            .collect();
+
+        if result.answer_count != 2 || returned != expected {
+            return Err(format!("Incorrect answers returned: {:?}", returned));
+        }
+
+        Ok(())
+    }
+
     #[sqlx::test]
     async fn get_questions_should_fail_if_database_error_occurs(
         pool: PgPool,
@@ -465,6 +794,7 @@ mod questions_tests {
     }
 
     #[sqlx::test]
+    #[allow(clippy::get_first)]
     async fn get_questions_should_succeed(pool: PgPool) -> Result<(), String> {
         let doa = QuestionsDaoImpl::new(pool);
 
@@ -488,4 +818,690 @@ mod questions_tests {
 
         Ok(())
     }
+
+    #[sqlx::test]
+    async fn get_questions_should_keep_their_etag_across_updates(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let mut created = Vec::new();
+
+        for _ in 0..5 {
+            let question = doa
+                .create_question(QuestionFields {
+                    title: "test title".to_owned(),
+                    description: "test description".to_owned(),
+                })
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+
+            created.push(question.question_uuid);
+        }
+
+        let before = doa.get_questions().await.map_err(|e| format!("{:?}", e))?;
+
+        // Updated rows move within the heap, which would reorder an unordered scan
+        doa.add_views(HashMap::from([(created[0], 1), (created[2], 1)]))
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let after = doa.get_questions().await.map_err(|e| format!("{:?}", e))?;
+
+        if etag(to_json(&after).as_bytes()) != etag(to_json(&before).as_bytes()) {
+            return Err(format!("The questions were reordered: {:?}", after));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn get_latest_questions_should_return_newest_first(pool: PgPool) -> Result<(), String> {
+        let doa = QuestionsDaoImpl::new(pool);
+
+        let mut created = Vec::new();
+
+        for title in ["first", "second", "third"] {
+            let question = doa
+                .create_question(QuestionFields {
+                    title: title.to_owned(),
+                    description: "test description".to_owned(),
+                })
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+
+            created.push(question.question_uuid);
+        }
+
+        let results = doa
+            .get_latest_questions(2)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let returned: Vec<QuestionId> = results
+            .into_iter()
+            .map(|question| question.question_uuid)
+            .collect();
+
+        if returned != vec![created[2], created[1]] {
+            return Err("Incorrect questions returned.".to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+mod backup_tests {
+    use sqlx::PgPool;
+    use tokio_stream::StreamExt;
+
+    use crate::{
+        models::{Answer, AnswerFields, DBError, ExportRecord, QuestionFields, QuestionId},
+        persistance::{
+            answers_dao::{AnswerDAO, DAO as AnswersDaoImpl},
+            backup_dao::{BackupDAO, DAO as BackupDaoImpl},
+            questions_dao::{QuestionDAO, DAO as QuestionsDaoImpl},
+        },
+    };
+
+    async fn export(doa: &BackupDaoImpl) -> Result<Vec<ExportRecord>, String> {
+        return doa
+            .export()
+            .collect::<Result<Vec<_>, _>>()
+            .await
+            .map_err(|e| format!("{:?}", e));
+    }
+
+    async fn seed(pool: &PgPool) -> Result<(), String> {
+        let question = QuestionsDaoImpl::new(pool.clone())
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        AnswersDaoImpl::new(pool.clone())
+            .create_answer(AnswerFields {
+                question_uuid: question.question_uuid,
+                content: "test content".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        Ok(())
+    }
+
+    async fn clear(pool: &PgPool) -> Result<(), String> {
+        sqlx::query("DELETE FROM questions")
+            .execute(pool)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn export_should_write_questions_before_answers(pool: PgPool) -> Result<(), String> {
+        seed(&pool).await?;
+
+        let records = export(&BackupDaoImpl::new(pool)).await?;
+
+        match records.as_slice() {
+            [ExportRecord::Question(question), ExportRecord::Answer(answer)]
+                if answer.detail.question_uuid == question.question_uuid =>
+            {
+                Ok(())
+            }
+            records => Err(format!("Unexpected export: {:?}", records)),
+        }
+    }
+
+    #[sqlx::test]
+    async fn import_should_restore_an_export(pool: PgPool) -> Result<(), String> {
+        let doa = BackupDaoImpl::new(pool.clone());
+
+        seed(&pool).await?;
+
+        let exported = export(&doa).await?;
+
+        clear(&pool).await?;
+
+        let summary = doa
+            .import(exported.clone(), false)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if summary.questions != 1 || summary.answers != 1 || summary.dry_run {
+            return Err(format!("Incorrect summary: {:?}", summary));
+        }
+
+        if export(&doa).await? != exported {
+            return Err("The import does not match the export.".to_owned());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn dry_run_import_should_keep_nothing(pool: PgPool) -> Result<(), String> {
+        let doa = BackupDaoImpl::new(pool.clone());
+
+        seed(&pool).await?;
+
+        let exported = export(&doa).await?;
+
+        clear(&pool).await?;
+
+        let summary = doa
+            .import(exported, true)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if summary.questions != 1 || summary.answers != 1 || !summary.dry_run {
+            return Err(format!("Incorrect summary: {:?}", summary));
+        }
+
+        if !export(&doa).await?.is_empty() {
+            return Err("Expected the dry run to be rolled back.".to_owned());
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn import_should_fail_with_dangling_answer(pool: PgPool) -> Result<(), String> {
+        let doa = BackupDaoImpl::new(pool);
+
+        let answer = Answer::new(AnswerFields {
+            question_uuid: QuestionId(uuid::Uuid::new_v4()),
+            content: "test content".to_owned(),
+        });
+
+        let result = doa.import(vec![ExportRecord::Answer(answer)], false).await;
+
+        if let Err(DBError::ForeignKeyViolation(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a foreign key violation but got the following result: {:?}",
+                result
+            ))
+        }
+    }
+
+    #[sqlx::test]
+    async fn import_should_fail_with_existing_records(pool: PgPool) -> Result<(), String> {
+        let doa = BackupDaoImpl::new(pool.clone());
+
+        seed(&pool).await?;
+
+        let result = doa.import(export(&doa).await?, false).await;
+
+        if let Err(DBError::UniqueViolation(_)) = result {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected a unique violation but got the following result: {:?}",
+                result
+            ))
+        }
+    }
+}
+
+mod unit_of_work_tests {
+    use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use crate::{
+        models::{AnswerFields, DBError, QuestionFields, QuestionId},
+        persistance::{
+            answers_dao::{AnswerDAO, DAO as AnswersDaoImpl},
+            cached_dao::{CachedDAO, DaoCache},
+            questions_dao::{QuestionDAO, DAO as QuestionsDaoImpl},
+            unit_of_work::{UnitOfWorkDAO, DAO as UnitOfWorkDaoImpl},
+        },
+    };
+
+    fn question() -> QuestionFields {
+        return QuestionFields {
+            title: "test title".to_owned(),
+            description: "test description".to_owned(),
+        };
+    }
+
+    fn cache() -> Arc<DaoCache> {
+        return Arc::new(DaoCache::new(
+            NonZeroUsize::new(16).unwrap(),
+            Duration::from_secs(60),
+        ));
+    }
+
+    #[sqlx::test]
+    async fn commit_should_store_every_change(pool: PgPool) -> Result<(), String> {
+        let work = UnitOfWorkDaoImpl::new(pool.clone(), cache())
+            .begin()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let question = work
+            .questions()
+            .create_question(question())
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        work.answers()
+            .create_answer(AnswerFields {
+                question_uuid: question.question_uuid,
+                content: "test content".to_owned(),
+            })
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        // Reads inside the unit of work see its changes before they are committed
+        let uncommitted = work
+            .questions()
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if uncommitted.answer_count != 1 {
+            return Err("The unit of work did not see its own answer.".to_owned());
+        }
+
+        work.commit().await.map_err(|e| format!("{:?}", e))?;
+
+        let stored = QuestionsDaoImpl::new(pool)
+            .get_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if stored != uncommitted {
+            return Err(format!("Incorrect question stored: {:?}", stored));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn failed_work_should_store_nothing(pool: PgPool) -> Result<(), String> {
+        let work = UnitOfWorkDaoImpl::new(pool.clone(), cache())
+            .begin()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let question = work
+            .questions()
+            .create_question(question())
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        let result = work
+            .answers()
+            .create_answer(AnswerFields {
+                question_uuid: QuestionId(Uuid::new_v4()),
+                content: "test content".to_owned(),
+            })
+            .await;
+
+        if !matches!(result, Err(DBError::ForeignKeyViolation(_))) {
+            return Err(format!("Expected a foreign key violation: {:?}", result));
+        }
+
+        // Dropped without committing
+        drop(work);
+
+        let result = QuestionsDaoImpl::new(pool)
+            .get_question(question.question_uuid)
+            .await;
+
+        if !matches!(result, Err(DBError::NotFound(_))) {
+            return Err(format!("The question was stored: {:?}", result));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn locked_question_should_hold_off_new_answers_until_commit(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let question = QuestionsDaoImpl::new(pool.clone())
+            .create_question(question())
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let work = UnitOfWorkDaoImpl::new(pool.clone(), cache())
+            .begin()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        work.questions()
+            .lock_question(question.question_uuid)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let answers = AnswersDaoImpl::new(pool);
+        let answer = AnswerFields {
+            question_uuid: question.question_uuid,
+            content: "test content".to_owned(),
+        };
+
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(200),
+            answers.create_answer(answer.clone()),
+        )
+        .await;
+
+        if blocked.is_ok() {
+            return Err("An answer was created while the question was locked.".to_owned());
+        }
+
+        work.commit().await.map_err(|e| format!("{:?}", e))?;
+
+        answers
+            .create_answer(answer)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn lock_question_should_fail_for_missing_question(pool: PgPool) -> Result<(), String> {
+        let work = UnitOfWorkDaoImpl::new(pool, cache())
+            .begin()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let result = work
+            .questions()
+            .lock_question(QuestionId(Uuid::new_v4()))
+            .await;
+
+        if !matches!(result, Err(DBError::NotFound(_))) {
+            return Err(format!("Incorrect result: {:?}", result));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn commit_should_evict_cached_reads(pool: PgPool) -> Result<(), String> {
+        let cache = cache();
+        let questions = CachedDAO::new(QuestionsDaoImpl::new(pool.clone()), cache.clone());
+
+        questions
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let work = UnitOfWorkDaoImpl::new(pool, cache)
+            .begin()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        work.questions()
+            .create_question(question())
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        // Reads before the commit cannot see the question yet, and may cache that
+        questions
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        work.commit().await.map_err(|e| format!("{:?}", e))?;
+
+        let listed = questions
+            .get_questions()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if listed.len() != 1 {
+            return Err(format!("Stale questions read: {:?}", listed));
+        }
+
+        Ok(())
+    }
+}
+
+mod idempotency_tests {
+    use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use crate::{
+        models::DBError,
+        persistance::{
+            cached_dao::DaoCache,
+            idempotency_dao::{Claim, IdempotencyDAO, DAO as IdempotencyDaoImpl},
+            unit_of_work::{UnitOfWorkDAO, DAO as UnitOfWorkDaoImpl},
+        },
+    };
+
+    fn unit_of_work_dao(pool: PgPool) -> UnitOfWorkDaoImpl {
+        return UnitOfWorkDaoImpl::new(
+            pool,
+            Arc::new(DaoCache::new(NonZeroUsize::MIN, Duration::ZERO)),
+        );
+    }
+
+    #[sqlx::test]
+    async fn retries_should_replay_completed_response(pool: PgPool) -> Result<(), String> {
+        let dao = IdempotencyDaoImpl::new(pool);
+        let claim = |fingerprint| dao.claim("key", fingerprint);
+
+        let Claim::Claimed(token) = claim("a").await.map_err(|e| format!("{:?}", e))? else {
+            return Err("Expected the key to be claimed.".to_owned());
+        };
+
+        dao.complete("key", token, r#"{"id": 1}"#)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        assert_eq!(
+            claim("a").await.map_err(|e| format!("{:?}", e))?,
+            Claim::Completed(serde_json::json!({"id": 1}))
+        );
+        assert_eq!(
+            claim("b").await.map_err(|e| format!("{:?}", e))?,
+            Claim::Mismatch
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn complete_should_require_token_of_claim(pool: PgPool) -> Result<(), String> {
+        let dao = IdempotencyDaoImpl::new(pool);
+
+        dao.claim("key", "a")
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let result = dao.complete("key", Uuid::new_v4(), "1").await;
+
+        if !matches!(result, Err(DBError::Other(_))) {
+            return Err(format!("Incorrect result: {:?}", result));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn concurrent_claim_should_wait_for_unit_of_work_holding_key(
+        pool: PgPool,
+    ) -> Result<(), String> {
+        let dao = unit_of_work_dao(pool);
+        let first = dao.begin().await.map_err(|e| format!("{:?}", e))?;
+        let second = dao.begin().await.map_err(|e| format!("{:?}", e))?;
+
+        let Claim::Claimed(token) = first
+            .idempotency()
+            .claim("key", "a")
+            .await
+            .map_err(|e| format!("{:?}", e))?
+        else {
+            return Err("Expected the key to be claimed.".to_owned());
+        };
+
+        first
+            .idempotency()
+            .complete("key", token, "1")
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let retry = tokio::spawn(async move {
+            return second.idempotency().claim("key", "a").await;
+        });
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        if retry.is_finished() {
+            return Err("The key was claimed again before the first claim ended.".to_owned());
+        }
+
+        first.commit().await.map_err(|e| format!("{:?}", e))?;
+
+        let claim = retry
+            .await
+            .map_err(|e| format!("{:?}", e))?
+            .map_err(|e| format!("{:?}", e))?;
+
+        if claim != Claim::Completed(serde_json::json!(1)) {
+            return Err(format!("Expected the response to be replayed: {:?}", claim));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rolled_back_claim_should_free_key(pool: PgPool) -> Result<(), String> {
+        let work = unit_of_work_dao(pool.clone())
+            .begin()
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        work.idempotency()
+            .claim("key", "a")
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        drop(work);
+
+        let claim = IdempotencyDaoImpl::new(pool)
+            .claim("key", "b")
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        if !matches!(claim, Claim::Claimed(_)) {
+            return Err(format!("Expected the key to be claimed: {:?}", claim));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn expired_key_should_be_claimed_again(pool: PgPool) -> Result<(), String> {
+        let dao = IdempotencyDaoImpl::new(pool.clone());
+
+        let Claim::Claimed(token) = dao
+            .claim("key", "a")
+            .await
+            .map_err(|e| format!("{:?}", e))?
+        else {
+            return Err("Expected the key to be claimed.".to_owned());
+        };
+
+        dao.complete("key", token, "1")
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        sqlx::query!("UPDATE idempotency_keys SET expires_at = CURRENT_TIMESTAMP")
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let claim = dao
+            .claim("key", "b")
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        match claim {
+            Claim::Claimed(reclaimed) if reclaimed != token => {}
+            claim => return Err(format!("Expected the key to be claimed anew: {:?}", claim)),
+        }
+
+        let deleted = dao.delete_expired().await.map_err(|e| format!("{:?}", e))?;
+
+        if deleted != 0 {
+            return Err("The reclaimed key was deleted.".to_owned());
+        }
+
+        Ok(())
+    }
+}
+
+mod errors_tests {
+    use sqlx::PgPool;
+
+    use crate::models::DBError;
+
+    #[sqlx::test]
+    async fn constraint_violations_should_be_classified(pool: PgPool) -> Result<(), String> {
+        sqlx::query("CREATE TABLE constrained (id INT PRIMARY KEY, amount INT CHECK (amount > 0))")
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        sqlx::query("INSERT INTO constrained VALUES (1, 1)")
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+
+        let duplicate = sqlx::query("INSERT INTO constrained VALUES (1, 1)")
+            .execute(&pool)
+            .await
+            .map_err(DBError::from);
+
+        if !matches!(duplicate, Err(DBError::UniqueViolation(_))) {
+            return Err(format!(
+                "Expected a unique violation but got: {:?}",
+                duplicate
+            ));
+        }
+
+        let negative = sqlx::query("INSERT INTO constrained VALUES (2, -1)")
+            .execute(&pool)
+            .await
+            .map_err(DBError::from);
+
+        if !matches!(negative, Err(DBError::CheckViolation(_))) {
+            return Err(format!(
+                "Expected a check violation but got: {:?}",
+                negative
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn closed_pool_should_be_classified_as_other(pool: PgPool) -> Result<(), String> {
+        pool.close().await;
+
+        let result = sqlx::query("SELECT 1")
+            .execute(&pool)
+            .await
+            .map_err(DBError::from);
+
+        if let Err(DBError::Other(_)) = result {
+            Ok(())
+        } else {
+            Err(format!("Expected an Other error but got: {:?}", result))
+        }
+    }
 }
@@ -0,0 +1,182 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{self, Arc},
+};
+
+use async_trait::async_trait;
+use sqlx::{pool::PoolConnection, PgConnection, PgPool, Postgres, Transaction};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::instrument;
+
+use crate::models::DBError;
+
+use super::{
+    answers_dao::{self, AnswerDAO},
+    cached_dao::{DaoCache, DeferredDAO, Eviction},
+    idempotency_dao::{self, IdempotencyDAO},
+    questions_dao::{self, QuestionDAO},
+    webhooks_dao::{self, WebhookDAO},
+};
+
+/// Where a DAO runs its queries: on any pooled connection, or inside a transaction it shares
+/// with the other DAOs of a unit of work.
+#[derive(Clone)]
+pub enum Database {
+    Pool(PgPool),
+    Transaction(Arc<Mutex<Transaction<'static, Postgres>>>),
+}
+
+/// A connection to run one query on, held until the query is done.
+pub enum Connection<'a> {
+    Pooled(Box<PoolConnection<Postgres>>),
+    Transaction(MutexGuard<'a, Transaction<'static, Postgres>>),
+}
+
+impl Database {
+    pub async fn acquire(&self) -> Result<Connection<'_>, DBError> {
+        return match self {
+            Self::Pool(pool) => Ok(Connection::Pooled(Box::new(
+                pool.acquire().await.map_err(DBError::from)?,
+            ))),
+            Self::Transaction(transaction) => Ok(Connection::Transaction(transaction.lock().await)),
+        };
+    }
+}
+
+impl Deref for Connection<'_> {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &Self::Target {
+        return match self {
+            Self::Pooled(connection) => connection,
+            Self::Transaction(transaction) => transaction,
+        };
+    }
+}
+
+impl DerefMut for Connection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return match self {
+            Self::Pooled(connection) => connection,
+            Self::Transaction(transaction) => transaction,
+        };
+    }
+}
+
+/// Changes made through several DAOs that are stored together or not at all. Dropping a unit of
+/// work without committing it discards its changes.
+///
+/// Its DAOs read around the DAO cache, and evict what they wrote from it once the work is
+/// committed.
+#[async_trait]
+pub trait UnitOfWork {
+    fn questions(&self) -> &(dyn QuestionDAO + Send + Sync);
+    fn answers(&self) -> &(dyn AnswerDAO + Send + Sync);
+    fn webhooks(&self) -> &(dyn WebhookDAO + Send + Sync);
+    fn idempotency(&self) -> &(dyn IdempotencyDAO + Send + Sync);
+    async fn commit(self: Box<Self>) -> Result<(), DBError>;
+}
+
+#[async_trait]
+pub trait UnitOfWorkDAO {
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork + Send + Sync>, DBError>;
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct DAO {
+    database: PgPool,
+    cache: Arc<DaoCache>,
+}
+
+impl DAO {
+    pub fn new(database: PgPool, cache: Arc<DaoCache>) -> Self {
+        return Self { database, cache };
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkDAO for DAO {
+    #[instrument(skip(self))]
+    async fn begin(&self) -> Result<Box<dyn UnitOfWork + Send + Sync>, DBError> {
+        let transaction = self.database.begin().await.map_err(DBError::from)?;
+        let transaction = Arc::new(Mutex::new(transaction));
+        let evictions = Arc::default();
+
+        return Ok(Box::new(PgUnitOfWork {
+            questions: DeferredDAO::new(
+                questions_dao::DAO::in_transaction(transaction.clone()),
+                Arc::clone(&evictions),
+            ),
+            answers: DeferredDAO::new(
+                answers_dao::DAO::in_transaction(transaction.clone()),
+                Arc::clone(&evictions),
+            ),
+            webhooks: webhooks_dao::DAO::in_transaction(transaction.clone()),
+            idempotency: idempotency_dao::DAO::in_transaction(transaction.clone()),
+            transaction,
+            evictions,
+            cache: self.cache.clone(),
+        }));
+    }
+}
+
+struct PgUnitOfWork {
+    transaction: Arc<Mutex<Transaction<'static, Postgres>>>,
+    questions: DeferredDAO<questions_dao::DAO>,
+    answers: DeferredDAO<answers_dao::DAO>,
+    webhooks: webhooks_dao::DAO,
+    idempotency: idempotency_dao::DAO,
+    evictions: Arc<sync::Mutex<Vec<Eviction>>>,
+    cache: Arc<DaoCache>,
+}
+
+#[async_trait]
+impl UnitOfWork for PgUnitOfWork {
+    fn questions(&self) -> &(dyn QuestionDAO + Send + Sync) {
+        return &self.questions;
+    }
+
+    fn answers(&self) -> &(dyn AnswerDAO + Send + Sync) {
+        return &self.answers;
+    }
+
+    fn webhooks(&self) -> &(dyn WebhookDAO + Send + Sync) {
+        return &self.webhooks;
+    }
+
+    fn idempotency(&self) -> &(dyn IdempotencyDAO + Send + Sync) {
+        return &self.idempotency;
+    }
+
+    #[instrument(skip_all)]
+    async fn commit(self: Box<Self>) -> Result<(), DBError> {
+        let Self {
+            transaction,
+            questions,
+            answers,
+            webhooks,
+            idempotency,
+            evictions,
+            cache,
+        } = *self;
+
+        drop((questions, answers, webhooks, idempotency));
+
+        let Ok(transaction) = Arc::try_unwrap(transaction) else {
+            unreachable!("Only the DAOs of a unit of work share its transaction");
+        };
+
+        transaction
+            .into_inner()
+            .commit()
+            .await
+            .map_err(DBError::from)?;
+
+        // Panic if another thread panicked while holding the lock
+        for eviction in evictions.lock().unwrap().drain(..) {
+            cache.evict(eviction);
+        }
+
+        return Ok(());
+    }
+}
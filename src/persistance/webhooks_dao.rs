@@ -0,0 +1,319 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use tokio::sync::Mutex;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    models::*,
+    telemetry::QueryTimer,
+    webhooks::{BATCH_SIZE, REQUEST_TIMEOUT},
+};
+
+use super::unit_of_work::Database;
+
+/// How long a claimed delivery is hidden from other workers. A worker sends its batch one
+/// delivery at a time, so this must exceed the time a whole batch of timeouts takes.
+const CLAIM_LEASE_SECONDS: f64 = 300.0;
+
+const _: () = assert!(CLAIM_LEASE_SECONDS > BATCH_SIZE as f64 * REQUEST_TIMEOUT.as_secs_f64());
+
+#[async_trait]
+pub trait WebhookDAO {
+    async fn create_webhook(&self, webhook: WebhookFields) -> Result<Webhook, DBError>;
+    async fn delete_webhook(&self, id: WebhookId) -> Result<(), DBError>;
+    async fn get_webhooks(&self) -> Result<Vec<Webhook>, DBError>;
+    async fn get_deliveries(&self, id: WebhookId) -> Result<Vec<WebhookDelivery>, DBError>;
+    /// Queues a delivery of the payload to every webhook subscribed to the event.
+    async fn enqueue_deliveries(
+        &self,
+        event: WebhookEvent,
+        payload: serde_json::Value,
+    ) -> Result<u64, DBError>;
+    /// Claims up to `limit` pending deliveries whose next attempt is due, counting that attempt.
+    async fn claim_due_deliveries(&self, limit: i64) -> Result<Vec<DueDelivery>, DBError>;
+    async fn mark_delivered(&self, id: Uuid, response_status: i32) -> Result<(), DBError>;
+    /// Records a failed attempt. Without a next attempt the delivery is dead-lettered.
+    async fn mark_failed(
+        &self,
+        id: Uuid,
+        response_status: Option<i32>,
+        error: String,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError>;
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct DAO {
+    database: Database,
+}
+
+impl DAO {
+    pub fn new(database: PgPool) -> Self {
+        return Self {
+            database: Database::Pool(database),
+        };
+    }
+
+    pub fn in_transaction(transaction: Arc<Mutex<Transaction<'static, Postgres>>>) -> Self {
+        return Self {
+            database: Database::Transaction(transaction),
+        };
+    }
+}
+
+fn parse<T: std::str::FromStr<Err = String>>(value: &str) -> Result<T, DBError> {
+    return value
+        .parse()
+        .map_err(|error: String| DBError::Other(error.into()));
+}
+
+fn parse_events(events: &[String]) -> Result<Vec<WebhookEvent>, DBError> {
+    return events.iter().map(|event| parse(event)).collect();
+}
+
+#[async_trait]
+impl WebhookDAO for DAO {
+    #[instrument(skip_all)]
+    async fn create_webhook(&self, webhook: WebhookFields) -> Result<Webhook, DBError> {
+        let _timer = QueryTimer::start("webhooks", "create_webhook");
+
+        let events: Vec<String> = webhook
+            .events
+            .iter()
+            .map(|event| event.as_str().to_owned())
+            .collect();
+
+        let record = sqlx::query!(
+            r#"
+                INSERT INTO webhook_subscriptions (url, events, secret)
+                VALUES ($1, $2, $3)
+                RETURNING id, url, events, created_at
+            "#,
+            webhook.url,
+            &events,
+            webhook.secret
+        )
+        .fetch_one(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(Webhook {
+            webhook_uuid: WebhookId(record.id),
+            url: record.url,
+            events: parse_events(&record.events)?,
+            created_at: record.created_at,
+        });
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_webhook(&self, id: WebhookId) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("webhooks", "delete_webhook");
+
+        let result = sqlx::query!("DELETE FROM webhook_subscriptions WHERE id = $1", id.0)
+            .execute(&mut *self.database.acquire().await?)
+            .await
+            .map_err(DBError::from)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DBError::NotFound(format!("No webhook with id: {}", id)));
+        }
+
+        return Ok(());
+    }
+
+    #[instrument(skip(self))]
+    async fn get_webhooks(&self) -> Result<Vec<Webhook>, DBError> {
+        let _timer = QueryTimer::start("webhooks", "get_webhooks");
+
+        let records = sqlx::query!(
+            "SELECT id, url, events, created_at FROM webhook_subscriptions ORDER BY created_at"
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return records
+            .into_iter()
+            .map(|record| {
+                return Ok(Webhook {
+                    webhook_uuid: WebhookId(record.id),
+                    url: record.url,
+                    events: parse_events(&record.events)?,
+                    created_at: record.created_at,
+                });
+            })
+            .collect();
+    }
+
+    #[instrument(skip(self))]
+    async fn get_deliveries(&self, id: WebhookId) -> Result<Vec<WebhookDelivery>, DBError> {
+        let _timer = QueryTimer::start("webhooks", "get_deliveries");
+
+        let webhook_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM webhook_subscriptions WHERE id = $1) AS "exists!""#,
+            id.0
+        )
+        .fetch_one(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        if !webhook_exists {
+            return Err(DBError::NotFound(format!("No webhook with id: {}", id)));
+        }
+
+        let records = sqlx::query!(
+            r#"
+                SELECT * FROM webhook_deliveries
+                WHERE subscription_id = $1
+                ORDER BY created_at DESC
+            "#,
+            id.0
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return records
+            .into_iter()
+            .map(|record| {
+                return Ok(WebhookDelivery {
+                    delivery_uuid: record.id,
+                    event: parse(&record.event)?,
+                    payload: record.payload,
+                    status: parse(&record.status)?,
+                    attempts: record.attempts,
+                    response_status: record.response_status,
+                    error: record.error,
+                    created_at: record.created_at,
+                    next_attempt_at: record.next_attempt_at,
+                    delivered_at: record.delivered_at,
+                });
+            })
+            .collect();
+    }
+
+    #[instrument(skip(self, payload))]
+    async fn enqueue_deliveries(
+        &self,
+        event: WebhookEvent,
+        payload: serde_json::Value,
+    ) -> Result<u64, DBError> {
+        let _timer = QueryTimer::start("webhooks", "enqueue_deliveries");
+
+        let result = sqlx::query!(
+            r#"
+                INSERT INTO webhook_deliveries (subscription_id, event, payload)
+                SELECT id, $1, $2 FROM webhook_subscriptions WHERE $1 = ANY(events)
+            "#,
+            event.as_str(),
+            payload
+        )
+        .execute(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(result.rows_affected());
+    }
+
+    #[instrument(skip(self))]
+    async fn claim_due_deliveries(&self, limit: i64) -> Result<Vec<DueDelivery>, DBError> {
+        let _timer = QueryTimer::start("webhooks", "claim_due_deliveries");
+
+        // Pushing the next attempt back hides claimed rows from other workers until the lease
+        // runs out, so a crashed worker's deliveries are eventually retried. Counting the attempt
+        // now means they are dead-lettered if that keeps happening
+        let records = sqlx::query!(
+            r#"
+                UPDATE webhook_deliveries d
+                SET next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $2),
+                    attempts = d.attempts + 1
+                FROM webhook_subscriptions s
+                WHERE s.id = d.subscription_id AND d.id IN (
+                    SELECT id FROM webhook_deliveries
+                    WHERE status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP
+                    ORDER BY next_attempt_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING d.id, d.event, d.payload, d.attempts, s.url, s.secret
+            "#,
+            limit,
+            CLAIM_LEASE_SECONDS
+        )
+        .fetch_all(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(records
+            .into_iter()
+            .map(|record| DueDelivery {
+                delivery_uuid: record.id,
+                event: record.event,
+                payload: record.payload,
+                attempts: record.attempts,
+                url: record.url,
+                secret: record.secret,
+            })
+            .collect());
+    }
+
+    #[instrument(skip(self))]
+    async fn mark_delivered(&self, id: Uuid, response_status: i32) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("webhooks", "mark_delivered");
+
+        sqlx::query!(
+            r#"
+                UPDATE webhook_deliveries
+                SET status = 'delivered', response_status = $2,
+                    error = NULL, delivered_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+            "#,
+            id,
+            response_status
+        )
+        .execute(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(());
+    }
+
+    #[instrument(skip(self, error))]
+    async fn mark_failed(
+        &self,
+        id: Uuid,
+        response_status: Option<i32>,
+        error: String,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError> {
+        let _timer = QueryTimer::start("webhooks", "mark_failed");
+
+        let status = match next_attempt_at {
+            Some(_) => DeliveryStatus::Pending,
+            None => DeliveryStatus::Dead,
+        };
+
+        sqlx::query!(
+            r#"
+                UPDATE webhook_deliveries
+                SET status = $2, response_status = $3, error = $4,
+                    next_attempt_at = COALESCE($5, next_attempt_at)
+                WHERE id = $1
+            "#,
+            id,
+            status.as_str(),
+            response_status,
+            error,
+            next_attempt_at
+        )
+        .execute(&mut *self.database.acquire().await?)
+        .await
+        .map_err(DBError::from)?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Instant;
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request},
+    http::{HeaderMap, HeaderName},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+use tracing::{info_span, Span};
+use tracing_subscriber::EnvFilter;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub fn init_tracing() {
+    let filter = EnvFilter::from_default_env();
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    // LOG_FORMAT=json switches to one JSON object per line for log shippers
+    if dotenvy::var("LOG_FORMAT").is_ok_and(|format| format == "json") {
+        subscriber.json().with_current_span(true).init();
+    } else {
+        subscriber.init();
+    }
+}
+
+fn request_id(headers: &HeaderMap) -> &str {
+    return headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+}
+
+/// Root span of every request, tagged with the ID set by `SetRequestIdLayer`.
+pub fn make_request_span(request: &Request<Body>) -> Span {
+    let request_id = request_id(request.headers());
+
+    return info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    );
+}
+
+/// Makes the request ID available to `current_request_id` until the response is produced.
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    let request_id = request_id(request.headers()).to_owned();
+
+    return REQUEST_ID.scope(request_id, next.run(request)).await;
+}
+
+pub fn current_request_id() -> Option<String> {
+    return REQUEST_ID
+        .try_with(|request_id| request_id.clone())
+        .ok()
+        .filter(|request_id| !request_id.is_empty());
+}
+
+pub fn install_metrics_recorder() -> PrometheusHandle {
+    // Panic if a global recorder has already been installed
+    return PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_owned()), LATENCY_BUCKETS)
+        .expect("Latency buckets must not be empty")
+        .install_recorder()
+        .expect("Could not install the Prometheus recorder");
+}
+
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    // Only used as a `route_layer`, so every request has a matched route
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_default();
+
+    let response = next.run(request).await;
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(start.elapsed().as_secs_f64());
+
+    return response;
+}
+
+pub fn record_pool_metrics(pool: &PgPool) {
+    let size = pool.size() as f64;
+    let idle = pool.num_idle() as f64;
+
+    gauge!("db_pool_connections", "state" => "idle").set(idle);
+    gauge!("db_pool_connections", "state" => "active").set(size - idle);
+}
+
+/// Records the lifetime of a DAO method call in `db_query_duration_seconds`.
+pub struct QueryTimer {
+    dao: &'static str,
+    method: &'static str,
+    start: Instant,
+}
+
+impl QueryTimer {
+    pub fn start(dao: &'static str, method: &'static str) -> Self {
+        return Self {
+            dao,
+            method,
+            start: Instant::now(),
+        };
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        histogram!("db_query_duration_seconds", "dao" => self.dao, "method" => self.method)
+            .record(self.start.elapsed().as_secs_f64());
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use metrics::counter;
+use tracing::error;
+
+use crate::{models::QuestionId, persistance::questions_dao::QuestionDAO};
+
+/// Counts question views in memory so reading a question never writes to the database.
+///
+/// Repeated views of the same question from the same address within `window` are counted
+/// once. Pending counts are written by `flush`, which is run periodically by `spawn_flusher`.
+pub struct ViewCounter {
+    window: Duration,
+    last_seen: Mutex<HashMap<(QuestionId, IpAddr), Instant>>,
+    pending: Mutex<HashMap<QuestionId, i64>>,
+}
+
+impl ViewCounter {
+    pub fn new(window: Duration) -> Self {
+        return Self {
+            window,
+            last_seen: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        };
+    }
+
+    /// Records a view of the question, returning whether it was counted.
+    pub fn record(&self, question: QuestionId, viewer: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut last_seen = self.last_seen.lock().unwrap();
+
+        if let Some(seen) = last_seen.get(&(question, viewer)) {
+            if now.duration_since(*seen) < self.window {
+                return false;
+            }
+        }
+
+        last_seen.insert((question, viewer), now);
+        *self.pending.lock().unwrap().entry(question).or_default() += 1;
+
+        return true;
+    }
+
+    /// Views of the question that have been counted but not flushed yet.
+    pub fn pending(&self, question: QuestionId) -> i64 {
+        return self
+            .pending
+            .lock()
+            .unwrap()
+            .get(&question)
+            .copied()
+            .unwrap_or_default();
+    }
+
+    /// Writes every pending count in a single statement and forgets viewers whose window has
+    /// expired. Counts stay pending until they are written, and are kept for the next flush if
+    /// the write fails.
+    pub async fn flush(&self, dao: &(dyn QuestionDAO + Send + Sync)) {
+        let now = Instant::now();
+
+        self.last_seen
+            .lock()
+            .unwrap()
+            .retain(|_, seen| now.duration_since(*seen) < self.window);
+
+        let views = self.pending.lock().unwrap().clone();
+
+        if views.is_empty() {
+            return;
+        }
+
+        let total: i64 = views.values().sum();
+
+        if let Err(error) = dao.add_views(views.clone()).await {
+            error!(error = ?error, "Could not flush question views");
+            return;
+        }
+
+        counter!("question_views_flushed_total").increment(total as u64);
+
+        // Views recorded during the write stay pending
+        let mut pending = self.pending.lock().unwrap();
+
+        for (question, count) in views {
+            if let Some(remaining) = pending.get_mut(&question) {
+                *remaining -= count;
+
+                if *remaining == 0 {
+                    pending.remove(&question);
+                }
+            }
+        }
+    }
+}
+
+pub fn spawn_flusher(
+    counter: Arc<ViewCounter>,
+    dao: Arc<dyn QuestionDAO + Send + Sync>,
+    period: Duration,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+            counter.flush(dao.as_ref()).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use sqlx::PgPool;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{models::QuestionFields, persistance::questions_dao::DAO as QuestionsDaoImpl};
+
+    const VIEWER: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn repeated_views_within_window_should_count_once() {
+        let counter = ViewCounter::new(Duration::from_secs(60));
+        let question = QuestionId(Uuid::new_v4());
+
+        assert!(counter.record(question, VIEWER));
+        assert!(!counter.record(question, VIEWER));
+        assert!(counter.record(question, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert_eq!(counter.pending(question), 2);
+    }
+
+    #[test]
+    fn views_after_window_should_count_again() {
+        let counter = ViewCounter::new(Duration::ZERO);
+        let question = QuestionId(Uuid::new_v4());
+
+        assert!(counter.record(question, VIEWER));
+        assert!(counter.record(question, VIEWER));
+        assert_eq!(counter.pending(question), 2);
+    }
+
+    #[test]
+    fn views_should_be_counted_per_question() {
+        let counter = ViewCounter::new(Duration::from_secs(60));
+        let first = QuestionId(Uuid::new_v4());
+        let second = QuestionId(Uuid::new_v4());
+
+        assert!(counter.record(first, VIEWER));
+        assert!(counter.record(second, VIEWER));
+        assert_eq!(counter.pending(first), 1);
+        assert_eq!(counter.pending(second), 1);
+    }
+
+    #[sqlx::test]
+    async fn flush_should_add_pending_views(pool: PgPool) {
+        let dao = QuestionsDaoImpl::new(pool);
+        let counter = ViewCounter::new(Duration::from_secs(60));
+        let question = dao
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .unwrap();
+
+        counter.record(question.question_uuid, VIEWER);
+        counter.flush(&dao).await;
+        counter.record(question.question_uuid, VIEWER);
+        counter.flush(&dao).await;
+
+        let detail = dao.get_question(question.question_uuid).await.unwrap();
+
+        assert_eq!(detail.view_count, 1);
+        assert_eq!(counter.pending(question.question_uuid), 0);
+    }
+
+    #[sqlx::test]
+    async fn flush_should_keep_views_pending_until_written(pool: PgPool) {
+        let dao = Arc::new(QuestionsDaoImpl::new(pool.clone()));
+        let counter = Arc::new(ViewCounter::new(Duration::from_secs(60)));
+        let question = dao
+            .create_question(QuestionFields {
+                title: "test title".to_owned(),
+                description: "test description".to_owned(),
+            })
+            .await
+            .unwrap();
+        let id = question.question_uuid;
+
+        // Hold the write off by locking the question
+        let mut lock = pool.begin().await.unwrap();
+
+        sqlx::query!("SELECT id FROM questions WHERE id = $1 FOR UPDATE", id.0)
+            .fetch_one(&mut *lock)
+            .await
+            .unwrap();
+
+        counter.record(id, VIEWER);
+
+        let flush = tokio::spawn({
+            let counter = counter.clone();
+            let dao = dao.clone();
+
+            async move { counter.flush(dao.as_ref()).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        assert!(!flush.is_finished());
+        assert_eq!(counter.pending(id), 1);
+
+        counter.record(id, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        lock.rollback().await.unwrap();
+        flush.await.unwrap();
+
+        assert_eq!(dao.get_question(id).await.unwrap().view_count, 1);
+        assert_eq!(counter.pending(id), 1);
+    }
+
+    #[sqlx::test]
+    async fn flush_should_keep_views_if_database_error_occurs(pool: PgPool) {
+        let dao = QuestionsDaoImpl::new(pool.clone());
+        let counter = ViewCounter::new(Duration::from_secs(60));
+        let question = QuestionId(Uuid::new_v4());
+
+        pool.close().await;
+
+        counter.record(question, VIEWER);
+        counter.flush(&dao).await;
+
+        assert_eq!(counter.pending(question), 1);
+    }
+}
@@ -0,0 +1,576 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use metrics::counter;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+    Url,
+};
+use serde_json::json;
+use sha2::Sha256;
+use tracing::{error, instrument, warn};
+
+use crate::{
+    activity::Activity,
+    models::{DBError, DueDelivery, WebhookEvent},
+    persistance::webhooks_dao::WebhookDAO,
+};
+
+pub const EVENT_HEADER: &str = "x-webhook-event";
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+/// `sha256=` followed by the hex HMAC-SHA256 of `{timestamp}.{body}` keyed with the secret.
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// Attempts after which a delivery is dead-lettered.
+const MAX_ATTEMPTS: i32 = 8;
+const BASE_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(6 * 60 * 60);
+pub const BATCH_SIZE: i64 = 20;
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    return format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+}
+
+/// Delay before the next attempt once `attempts` attempts have failed.
+fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+
+    return BASE_BACKOFF
+        .saturating_mul(2u32.pow(exponent))
+        .min(MAX_BACKOFF);
+}
+
+/// The event and body delivered for an activity, if webhooks can subscribe to it.
+pub fn payload(activity: Activity) -> Option<(WebhookEvent, serde_json::Value)> {
+    let (event, data) = match activity {
+        Activity::QuestionCreated(question) => (WebhookEvent::QuestionCreated, json!(question)),
+        Activity::QuestionDeleted(question_uuid) => (
+            WebhookEvent::QuestionDeleted,
+            json!({ "question_uuid": question_uuid }),
+        ),
+        Activity::AnswerCreated(answer) => (WebhookEvent::AnswerCreated, json!(answer)),
+        Activity::AnswerDeleted(answer_uuid) => (
+            WebhookEvent::AnswerDeleted,
+            json!({ "answer_uuid": answer_uuid }),
+        ),
+        Activity::ViewersChanged { .. } => return None,
+    };
+
+    return Some((event, json!({ "event": event, "data": data })));
+}
+
+/// Whether deliveries may reach `address`. Receivers on this host or its internal networks are
+/// refused, so webhooks cannot be used to probe them.
+fn is_public(address: IpAddr) -> bool {
+    return match address {
+        IpAddr::V4(address) => {
+            let [first, second, ..] = address.octets();
+            // 100.64.0.0/10 is shared by carrier-grade NAT
+            let shared = first == 100 && (64..128).contains(&second);
+
+            !(address.is_loopback()
+                || address.is_private()
+                || address.is_link_local()
+                || address.is_unspecified()
+                || address.is_broadcast()
+                || address.is_multicast()
+                || shared)
+        }
+        IpAddr::V6(address) => match address.to_ipv4_mapped() {
+            Some(address) => is_public(IpAddr::V4(address)),
+            None => {
+                !(address.is_loopback()
+                    || address.is_unique_local()
+                    || address.is_unicast_link_local()
+                    || address.is_unspecified()
+                    || address.is_multicast())
+            }
+        },
+    };
+}
+
+/// Resolves receiver host names to their public addresses only. Checking the addresses actually
+/// connected to, rather than the URL, also covers names that are rebound after being checked.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        return Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+
+            let addresses: Addrs = Box::new(addresses.into_iter());
+
+            return Ok(addresses);
+        });
+    }
+}
+
+/// Sends deliveries over HTTP, to public addresses only.
+pub struct Sender {
+    client: reqwest::Client,
+    public_only: bool,
+}
+
+impl Sender {
+    pub fn public() -> Self {
+        // Redirects are not followed, as they could lead to any address
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build();
+
+        return Self {
+            // Panic if the TLS backend cannot be initialized
+            client: client.expect("Could not build the webhook HTTP client"),
+            public_only: true,
+        };
+    }
+
+    /// Sends to any address, so tests can deliver to receivers on this host.
+    #[cfg(test)]
+    fn unrestricted() -> Self {
+        return Self {
+            client: reqwest::Client::builder()
+                .redirect(Policy::none())
+                .build()
+                .unwrap(),
+            public_only: false,
+        };
+    }
+
+    /// Fails for URLs whose host is an address deliveries may not reach. Host names are checked
+    /// once resolved instead.
+    fn check_target(&self, url: &str) -> Result<(), String> {
+        let url = Url::parse(url).map_err(|error| error.to_string())?;
+        // IPv6 hosts are written in brackets
+        let host = url.host_str().unwrap_or_default();
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let Ok(address) = host.parse::<IpAddr>() else {
+            return Ok(());
+        };
+
+        if self.public_only && !is_public(address) {
+            return Err(format!("{} is not a public address", address));
+        }
+
+        return Ok(());
+    }
+}
+
+pub fn spawn_worker(dao: Arc<dyn WebhookDAO + Send + Sync>, period: Duration) {
+    let sender = Sender::public();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(error) = deliver_due(dao.as_ref(), &sender).await {
+                error!(error = ?error, "Could not claim webhook deliveries");
+            }
+        }
+    });
+}
+
+/// Sends every delivery that is due, returning how many were attempted.
+pub async fn deliver_due(
+    dao: &(dyn WebhookDAO + Send + Sync),
+    sender: &Sender,
+) -> Result<usize, DBError> {
+    let deliveries = dao.claim_due_deliveries(BATCH_SIZE).await?;
+    let count = deliveries.len();
+
+    for delivery in deliveries {
+        let id = delivery.delivery_uuid;
+        let attempts = delivery.attempts;
+
+        // Attempts are counted when claimed, so one that never finished, for example because
+        // the worker crashed, still uses up an attempt
+        if attempts > MAX_ATTEMPTS {
+            counter!("webhook_deliveries_total", "outcome" => "dead").increment(1);
+            warn!(delivery = %id, "Webhook delivery ran out of attempts");
+
+            if let Err(error) = dao
+                .mark_failed(id, None, "Ran out of attempts".to_owned(), None)
+                .await
+            {
+                error!(error = ?error, delivery = %id, "Could not record webhook delivery attempt");
+            }
+
+            continue;
+        }
+
+        let result = match send(sender, &delivery).await {
+            Ok(status) => {
+                counter!("webhook_deliveries_total", "outcome" => "delivered").increment(1);
+                dao.mark_delivered(id, status).await
+            }
+            Err((status, message)) if attempts < MAX_ATTEMPTS => {
+                counter!("webhook_deliveries_total", "outcome" => "failed").increment(1);
+                let next_attempt_at = Utc::now() + backoff(attempts);
+
+                dao.mark_failed(id, status, message, Some(next_attempt_at))
+                    .await
+            }
+            Err((status, message)) => {
+                counter!("webhook_deliveries_total", "outcome" => "dead").increment(1);
+                warn!(delivery = %id, "Webhook delivery ran out of attempts");
+
+                dao.mark_failed(id, status, message, None).await
+            }
+        };
+
+        // The claim lease expires on its own, so the delivery is retried later anyway
+        if let Err(error) = result {
+            error!(error = ?error, delivery = %id, "Could not record webhook delivery attempt");
+        }
+    }
+
+    return Ok(count);
+}
+
+/// Posts the delivery, returning the response status on success or the status (if any) and
+/// a description of the failure.
+#[instrument(skip_all, fields(delivery = %delivery.delivery_uuid, event = %delivery.event))]
+async fn send(sender: &Sender, delivery: &DueDelivery) -> Result<i32, (Option<i32>, String)> {
+    sender
+        .check_target(&delivery.url)
+        .map_err(|message| (None, message))?;
+
+    let body = delivery.payload.to_string().into_bytes();
+    let timestamp = Utc::now().timestamp();
+
+    let response = sender
+        .client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.delivery_uuid.to_string())
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(SIGNATURE_HEADER, sign(&delivery.secret, timestamp, &body))
+        .body(body)
+        .send()
+        .await
+        .map_err(|error| (None, error.to_string()))?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        return Err((
+            Some(status.as_u16().into()),
+            format!("Receiver responded with {}", status),
+        ));
+    }
+
+    return Ok(status.as_u16().into());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use sqlx::PgPool;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::{
+        models::{DeliveryStatus, QuestionId, Webhook, WebhookFields},
+        persistance::webhooks_dao::DAO as WebhooksDaoImpl,
+    };
+
+    const SECRET: &str = "a secret that is long enough";
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    /// Starts a local receiver that answers every delivery with `status`.
+    async fn receiver(status: StatusCode) -> (String, Received) {
+        let received: Received = Arc::default();
+        let log = received.clone();
+        let router = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                log.lock().unwrap().push((headers, body));
+
+                return status;
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        return (url, received);
+    }
+
+    async fn subscribe(dao: &WebhooksDaoImpl, url: String) -> Webhook {
+        return dao
+            .create_webhook(WebhookFields {
+                url,
+                events: vec![WebhookEvent::QuestionDeleted],
+                secret: SECRET.to_owned(),
+            })
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn backoff_should_grow_exponentially_up_to_the_limit() {
+        assert_eq!(backoff(1), Duration::from_secs(30));
+        assert_eq!(backoff(2), Duration::from_secs(60));
+        assert_eq!(backoff(4), Duration::from_secs(240));
+        assert_eq!(backoff(30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn signature_should_depend_on_secret_timestamp_and_body() {
+        let signature = sign(SECRET, 1, b"{}");
+
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+        assert_ne!(signature, sign("another secret value", 1, b"{}"));
+        assert_ne!(signature, sign(SECRET, 2, b"{}"));
+        assert_ne!(signature, sign(SECRET, 1, b"[]"));
+    }
+
+    #[test]
+    fn only_public_addresses_should_be_reachable() {
+        for address in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(address.parse().unwrap()), "{}", address);
+        }
+
+        for address in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c"] {
+            assert!(is_public(address.parse().unwrap()), "{}", address);
+        }
+    }
+
+    #[sqlx::test]
+    async fn delivery_to_internal_address_should_be_refused(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool);
+        let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+        let webhook = subscribe(&dao, url).await;
+
+        dao.enqueue_deliveries(WebhookEvent::QuestionDeleted, json!({}))
+            .await
+            .unwrap();
+
+        deliver_due(&dao, &Sender::public()).await.unwrap();
+
+        assert!(received.lock().unwrap().is_empty());
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+        assert_eq!(deliveries[0].response_status, None);
+    }
+
+    #[sqlx::test]
+    async fn delivery_to_name_of_internal_address_should_be_refused(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool);
+        let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+        let webhook = subscribe(&dao, url.replace("127.0.0.1", "localhost")).await;
+
+        dao.enqueue_deliveries(WebhookEvent::QuestionDeleted, json!({}))
+            .await
+            .unwrap();
+
+        deliver_due(&dao, &Sender::public()).await.unwrap();
+
+        assert!(received.lock().unwrap().is_empty());
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+        assert_eq!(deliveries[0].response_status, None);
+    }
+
+    #[sqlx::test]
+    async fn redirects_should_not_be_followed(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool);
+        let (url, received) = receiver(StatusCode::TEMPORARY_REDIRECT).await;
+        let webhook = subscribe(&dao, url).await;
+
+        dao.enqueue_deliveries(WebhookEvent::QuestionDeleted, json!({}))
+            .await
+            .unwrap();
+
+        deliver_due(&dao, &Sender::unrestricted()).await.unwrap();
+
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+        assert_eq!(deliveries[0].response_status, Some(307));
+    }
+
+    #[sqlx::test]
+    async fn due_delivery_should_be_signed_and_logged(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool);
+        let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+        let webhook = subscribe(&dao, url).await;
+        let (event, payload) =
+            payload(Activity::QuestionDeleted(QuestionId(uuid::Uuid::new_v4()))).unwrap();
+
+        dao.enqueue_deliveries(event, payload).await.unwrap();
+
+        let delivered = deliver_due(&dao, &Sender::unrestricted()).await.unwrap();
+
+        assert_eq!(delivered, 1);
+
+        let (headers, body) = received.lock().unwrap().pop().unwrap();
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+
+        assert_eq!(headers[EVENT_HEADER], "question.deleted");
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign(SECRET, timestamp, &body)
+        );
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts, 1);
+        assert_eq!(deliveries[0].response_status, Some(204));
+    }
+
+    #[sqlx::test]
+    async fn failed_delivery_should_be_retried_later(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool);
+        let (url, received) = receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let webhook = subscribe(&dao, url).await;
+
+        dao.enqueue_deliveries(WebhookEvent::QuestionDeleted, json!({}))
+            .await
+            .unwrap();
+
+        deliver_due(&dao, &Sender::unrestricted()).await.unwrap();
+
+        // Not due again until the backoff has passed
+        assert_eq!(deliver_due(&dao, &Sender::unrestricted()).await.unwrap(), 0);
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+        assert_eq!(deliveries[0].attempts, 1);
+        assert_eq!(deliveries[0].response_status, Some(500));
+        assert!(deliveries[0].next_attempt_at > Utc::now());
+    }
+
+    #[sqlx::test]
+    async fn last_failed_attempt_should_dead_letter_delivery(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool.clone());
+        let (url, _) = receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let webhook = subscribe(&dao, url).await;
+
+        dao.enqueue_deliveries(WebhookEvent::QuestionDeleted, json!({}))
+            .await
+            .unwrap();
+        sqlx::query!(
+            "UPDATE webhook_deliveries SET attempts = $1",
+            MAX_ATTEMPTS - 1
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        deliver_due(&dao, &Sender::unrestricted()).await.unwrap();
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries[0].status, DeliveryStatus::Dead);
+        assert_eq!(deliveries[0].attempts, MAX_ATTEMPTS);
+    }
+
+    #[sqlx::test]
+    async fn claimed_attempts_that_never_finished_should_dead_letter_delivery(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool.clone());
+        let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+        let webhook = subscribe(&dao, url).await;
+
+        dao.enqueue_deliveries(WebhookEvent::QuestionDeleted, json!({}))
+            .await
+            .unwrap();
+
+        // Workers that crash after claiming leave the delivery to be claimed again once the
+        // lease runs out
+        for _ in 0..MAX_ATTEMPTS {
+            assert_eq!(dao.claim_due_deliveries(BATCH_SIZE).await.unwrap().len(), 1);
+            sqlx::query!("UPDATE webhook_deliveries SET next_attempt_at = CURRENT_TIMESTAMP")
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        deliver_due(&dao, &Sender::unrestricted()).await.unwrap();
+
+        assert!(received.lock().unwrap().is_empty());
+
+        let deliveries = dao.get_deliveries(webhook.webhook_uuid).await.unwrap();
+
+        assert_eq!(deliveries[0].status, DeliveryStatus::Dead);
+        assert_eq!(deliveries[0].attempts, MAX_ATTEMPTS + 1);
+    }
+
+    #[sqlx::test]
+    async fn events_should_only_be_queued_for_subscribed_webhooks(pool: PgPool) {
+        let dao = WebhooksDaoImpl::new(pool);
+        let webhook = subscribe(&dao, "http://127.0.0.1:1/hook".to_owned()).await;
+
+        let queued = dao
+            .enqueue_deliveries(WebhookEvent::AnswerCreated, json!({}))
+            .await
+            .unwrap();
+
+        assert_eq!(queued, 0);
+        assert!(dao
+            .get_deliveries(webhook.webhook_uuid)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}